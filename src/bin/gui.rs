@@ -1,5 +1,6 @@
 // #![cfg_attr(target_os = "windows", windows_subsystem = "windows")]
-use clipvault::{db, gui};
+use clipvault::{db, gui, picker};
+use clipvault::utils::desktop::position_near_cursor;
 use eframe::{NativeOptions, egui, icon_data::from_png_bytes};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -9,13 +10,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let icon_bytes = include_bytes!("../../assets/clipboard.png");
     let icon = from_png_bytes(icon_bytes).expect("Invalid PNG");
 
+    let gui_db = db::init_db()?;
+
+    // `gui --picker` is the quick-paste popup the daemon opens from its hotkey
+    if std::env::args().nth(1).as_deref() == Some("--picker") {
+        let position = position_near_cursor(picker::PICKER_SIZE);
+        let mut viewport = egui::ViewportBuilder::default()
+            .with_icon(icon)
+            .with_inner_size(picker::PICKER_SIZE)
+            .with_decorations(false)
+            .with_resizable(false)
+            .with_always_on_top()
+            .with_taskbar(false);
+        if let Some(position) = position {
+            viewport = viewport.with_position(position);
+        }
+        let picker_options = NativeOptions { viewport, centered: position.is_none(), ..Default::default() };
+        eframe::run_native(
+            "ClipVault Picker",
+            picker_options,
+            Box::new(|cc| Ok(Box::new(picker::PickerApp::new(gui_db, &cc.egui_ctx)))),
+        )?;
+        return Ok(());
+    }
+
     let native_options = NativeOptions {
         viewport: egui::ViewportBuilder::default().with_icon(icon),
         ..Default::default()
     };
 
-    let gui_db = db::init_db()?;
-
     eframe::run_native(
         "ClipVault",
         native_options,
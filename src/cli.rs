@@ -0,0 +1,563 @@
+// `clipvault <command>` gives scripts access to the vault without touching SQLite directly.
+// Without a command the tray daemon starts as before.
+use std::{ error::Error, fs, io::{ self, BufRead, BufReader, Read, Write }, path::PathBuf, process::{ Command, Stdio } };
+use clap::{ Parser, Subcommand };
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::clipboard::{ self, CapturedClip };
+use crate::db;
+use crate::models::{ Clip, ClipInfo, ClipKind, ClipOrigin, PauseState, Selection, VaultExport };
+use crate::settings::Settings;
+use crate::utils::formatting::{ format_size, format_timestamp };
+use crate::utils::images::decode_png;
+use crate::utils::split::{ SplitMode, split_clip };
+
+// Exit codes are part of the interface, scripts rely on them
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+// Bad arguments, reported by clap
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NOT_FOUND: i32 = 3;
+pub const EXIT_CLIPBOARD: i32 = 4;
+
+// Written by the detached `copy --foreground` process once it serves the clipboard
+const COPY_SERVED: &str = "served";
+
+/// ClipVault clipboard history. Run without a command to start the tray app.
+#[derive(Parser, Debug)]
+#[command(name = "clipvault", version)]
+pub struct Cli {
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CliCommand {
+    /// List recent clips, pinned clips first
+    List {
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+        /// Only clips with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+    },
+    /// Search clips, with the same filters as the search box (app:, window:, host:)
+    Search {
+        query: String,
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show one clip in full
+    Show { id: i64 },
+    /// Add a text clip from the arguments, or from stdin when there are none
+    Add {
+        text: Vec<String>,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long)]
+        note: Option<String>,
+        /// Tag to add, created if missing; can be repeated
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+        #[arg(long)]
+        pin: bool,
+    },
+    /// Put a clip on the clipboard
+    Copy {
+        id: i64,
+        /// Copy the link as it was before tracking parameters were removed
+        #[arg(long)]
+        original: bool,
+        // Serve the clipboard from this process instead of a detached one
+        #[arg(long, hide = true)]
+        foreground: bool,
+    },
+    /// Pin clips
+    Pin {
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Unpin clips
+    Unpin {
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Add tags to a clip, creating tags that don't exist yet
+    Tag {
+        id: i64,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a clip
+    Untag {
+        id: i64,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Split a text clip into new clips, one per line unless another mode is given
+    Split {
+        id: i64,
+        /// One clip per line
+        #[arg(long, group = "mode")]
+        lines: bool,
+        /// Split at every occurrence of this text
+        #[arg(short, long, group = "mode")]
+        delimiter: Option<String>,
+        /// One clip per match of this regular expression
+        #[arg(short, long, group = "mode")]
+        regex: Option<String>,
+        /// Tag to add to the new clips, created if missing; they keep the clip's tags too
+        #[arg(short, long)]
+        tag: Option<String>,
+    },
+    /// Delete clips
+    Delete {
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Count clips by kind and show the database size
+    Stats,
+    /// Write the whole vault as JSON, to stdout unless a file is given
+    Export {
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Add the clips of an export file ("-" for stdin), skipping ones already here
+    Import { file: PathBuf },
+    /// Stop recording, for a number of minutes if given
+    Pause {
+        #[arg(value_parser = clap::value_parser!(u32).range(1..=PauseState::MAX_INCOGNITO_MINUTES))]
+        minutes: Option<u32>,
+    },
+    /// Start recording again
+    Resume,
+    /// Show whether clips are being recorded
+    Status,
+}
+
+enum CliError {
+    // Arguments clap can't check, like a regular expression
+    Usage(String),
+    NotFound(String),
+    Clipboard(String),
+    Failed(Box<dyn Error>),
+}
+
+impl<E: Error + 'static> From<E> for CliError {
+    fn from(e: E) -> Self {
+        CliError::Failed(Box::new(e))
+    }
+}
+
+type CliResult = Result<(), CliError>;
+
+// Run a command and return the process exit code
+pub fn run(command: CliCommand, json: bool) -> i32 {
+    db::set_quiet(true);
+    let result = db::init_db().map_err(CliError::from).and_then(|conn| execute(&conn, command, json));
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(CliError::Usage(e)) => {
+            eprintln!("clipvault: {}", e);
+            EXIT_USAGE
+        }
+        Err(CliError::NotFound(what)) => {
+            eprintln!("clipvault: {} not found", what);
+            EXIT_NOT_FOUND
+        }
+        Err(CliError::Clipboard(e)) => {
+            eprintln!("clipvault: clipboard error: {}", e);
+            EXIT_CLIPBOARD
+        }
+        Err(CliError::Failed(e)) => {
+            eprintln!("clipvault: {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn execute(conn: &Connection, command: CliCommand, json: bool) -> CliResult {
+    match command {
+        CliCommand::List { limit, tag } => {
+            let rows = match tag {
+                Some(name) => {
+                    let tag_id = db::find_tag(conn, &name)?.ok_or(CliError::NotFound(format!("tag {:?}", name)))?;
+                    db::load_clips_for_tag(conn, &tag_id)?.into_iter().take(limit).collect()
+                }
+                None => db::load_recent_clips(conn, limit)?,
+            };
+            print_clips(conn, rows.into_iter().map(Clip::from_tuple).collect(), json)
+        }
+        CliCommand::Search { query, limit } => {
+            let rows = db::search_clips(conn, &query, Some(limit))?;
+            print_clips(conn, rows.into_iter().map(Clip::from_tuple).collect(), json)
+        }
+        CliCommand::Show { id } => {
+            let clip = load_clip(conn, id)?;
+            let output = ClipInfo::new(clip, clip_tags(conn, id)?);
+            if json {
+                return print_json(&output);
+            }
+            println!("id:       {}", output.id);
+            println!("kind:     {}", output.kind);
+            println!("copied:   {}", format_timestamp(output.timestamp));
+            if output.pinned {
+                println!("pinned:   yes");
+            }
+            if output.selection != Selection::Clipboard.as_str() {
+                println!("from:     {} selection", output.selection);
+            }
+            if let Some(summary) = output.origin.as_ref().and_then(ClipOrigin::summary) {
+                println!("app:      {}", summary);
+            }
+            if !output.tags.is_empty() {
+                println!("tags:     {}", output.tags.join(", "));
+            }
+            if let Some(title) = &output.title {
+                println!("title:    {}", title);
+            }
+            if let Some(note) = &output.note {
+                println!("note:     {}", note);
+            }
+            if let Some(original) = &output.original {
+                println!("original: {}", original);
+            }
+            println!();
+            println!("{}", output.content);
+            Ok(())
+        }
+        CliCommand::Add { text, title, note, tags, pin } => {
+            let text = if text.is_empty() {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                // `echo text | clipvault add` shouldn't store echo's newline
+                input.strip_suffix('\n').map(|s| s.strip_suffix('\r').unwrap_or(s)).unwrap_or(&input).to_string()
+            } else {
+                text.join(" ")
+            };
+            if text.trim().is_empty() {
+                return Err(CliError::Failed("nothing to add".into()));
+            }
+
+            let id = db::add_text_clip(conn, &text, title.as_deref(), note.as_deref(), &tags, pin)?;
+
+            if json {
+                print_json(&ClipInfo::new(load_clip(conn, id)?, clip_tags(conn, id)?))
+            } else {
+                println!("{}", id);
+                Ok(())
+            }
+        }
+        CliCommand::Copy { id, original, foreground } => {
+            let clip = load_clip(conn, id)?;
+            let captured = copy_source(conn, clip, original).map_err(CliError::NotFound)?;
+            copy_clip(&captured, id, original, foreground)?;
+            if json {
+                print_json(&serde_json::json!({ "copied": id }))
+            } else {
+                Ok(())
+            }
+        }
+        CliCommand::Pin { ids } => set_pinned(conn, &ids, true),
+        CliCommand::Unpin { ids } => set_pinned(conn, &ids, false),
+        CliCommand::Tag { id, tags } => {
+            load_clip(conn, id)?;
+            db::tag_clip(conn, id, &tags)?;
+            print_tags(conn, id, json)
+        }
+        CliCommand::Untag { id, tags } => {
+            load_clip(conn, id)?;
+            for name in &tags {
+                if let Some(tag_id) = db::find_tag(conn, name)? {
+                    db::remove_tag_from_clip(conn, id, tag_id)?;
+                }
+            }
+            print_tags(conn, id, json)
+        }
+        CliCommand::Split { id, lines: _, delimiter, regex, tag } => {
+            let clip = load_clip(conn, id)?;
+            if clip.kind != ClipKind::Text {
+                return Err(CliError::Failed(format!("clip {} is {}, only text can be split", id, clip.kind.as_str()).into()));
+            }
+            let (mode, pattern) = match (delimiter, regex) {
+                (Some(delimiter), _) => (SplitMode::Delimiter, delimiter),
+                (_, Some(regex)) => (SplitMode::Regex, regex),
+                _ => (SplitMode::Lines, String::new()),
+            };
+            let pieces = split_clip(&clip.content, mode, &pattern)
+                .map_err(|e| CliError::Usage(format!("invalid regex: {}", e)))?;
+            if pieces.is_empty() {
+                return Err(CliError::Failed("nothing to split".into()));
+            }
+            let tag_id = tag.map(|name| db::find_or_create_tag(conn, &name)).transpose()?.map(|(tag_id, _)| tag_id);
+
+            let ids = db::save_split_clips(conn, id, &pieces, tag_id)?;
+            if json {
+                let clips = ids.iter().map(|id| load_clip(conn, *id)).collect::<Result<Vec<_>, _>>()?;
+                return print_clips(conn, clips, true);
+            }
+            for id in ids {
+                println!("{}", id);
+            }
+            Ok(())
+        }
+        CliCommand::Delete { ids } => {
+            let mut missing = Vec::new();
+            for id in &ids {
+                if db::delete_clip(conn, *id)? == 0 {
+                    missing.push(id.to_string());
+                }
+            }
+            if json {
+                print_json(&serde_json::json!({ "deleted": ids.len() - missing.len() }))?;
+            }
+            if missing.is_empty() {
+                Ok(())
+            } else {
+                Err(CliError::NotFound(format!("clip {}", missing.join(", "))))
+            }
+        }
+        CliCommand::Stats => {
+            let stats = db::load_stats(conn)?;
+            if json {
+                return print_json(&stats);
+            }
+            println!("clips:    {}", stats.clips);
+            println!("text:     {}", stats.text);
+            println!("images:   {}", stats.images);
+            println!("files:    {}", stats.files);
+            println!("pinned:   {}", stats.pinned);
+            println!("primary:  {}", stats.primary);
+            println!("tags:     {}", stats.tags);
+            if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+                println!("oldest:   {}", format_timestamp(oldest));
+                println!("newest:   {}", format_timestamp(newest));
+            }
+            println!("database: {}", format_size(stats.database_bytes));
+            Ok(())
+        }
+        CliCommand::Export { output } => {
+            let export = db::export_vault(conn)?;
+            match output {
+                Some(path) => {
+                    let file = io::BufWriter::new(fs::File::create(&path)?);
+                    serde_json::to_writer_pretty(file, &export)?;
+                    if !json {
+                        eprintln!("Exported {} clip(s) to {}", export.clips.len(), path.display());
+                    }
+                }
+                None => {
+                    let mut stdout = io::stdout().lock();
+                    serde_json::to_writer_pretty(&mut stdout, &export)?;
+                    writeln!(stdout)?;
+                }
+            }
+            Ok(())
+        }
+        CliCommand::Import { file } => {
+            let input = if file.as_os_str() == "-" {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                input
+            } else {
+                fs::read_to_string(&file)?
+            };
+            let export: VaultExport = serde_json::from_str(&input)?;
+            let summary = db::import_vault(conn, &export).map_err(CliError::Failed)?;
+            if json {
+                return print_json(&summary);
+            }
+            println!(
+                "Imported {} clip(s), skipped {} already in the vault, created {} tag(s)",
+                summary.imported, summary.skipped, summary.tags_created
+            );
+            Ok(())
+        }
+        CliCommand::Pause { minutes } => {
+            let now = chrono::Utc::now().timestamp();
+            let state = match minutes {
+                Some(minutes) => PauseState::incognito_for(minutes.into(), now),
+                None => PauseState::Paused,
+            };
+            set_pause_state(conn, state).map_err(CliError::Failed)?;
+            print_pause_state(state, json)
+        }
+        CliCommand::Resume => {
+            set_pause_state(conn, PauseState::Recording).map_err(CliError::Failed)?;
+            print_pause_state(PauseState::Recording, json)
+        }
+        CliCommand::Status => print_pause_state(db::load_pause_state(conn)?, json),
+    }
+}
+
+fn load_clip(conn: &Connection, id: i64) -> Result<Clip, CliError> {
+    db::load_clip(conn, id)?
+        .map(Clip::from_tuple)
+        .ok_or(CliError::NotFound(format!("clip {}", id)))
+}
+
+fn clip_tags(conn: &Connection, id: i64) -> Result<Vec<String>, CliError> {
+    Ok(db::load_clip_tags(conn)?.remove(&id).unwrap_or_default())
+}
+
+// A stored clip as the monitor would have captured it, so it can be put back on the clipboard
+pub fn captured_clip(conn: &Connection, clip: &Clip) -> Option<CapturedClip> {
+    match clip.kind {
+        ClipKind::Text => Some(CapturedClip::Text {
+            formats: db::load_clip_formats(conn, clip.id).unwrap_or_default(),
+            text: clip.content.clone(),
+            selection: clip.selection,
+        }),
+        ClipKind::Image => {
+            let image = decode_png(&db::load_clip_image(conn, clip.id).ok()?).ok()?;
+            Some(CapturedClip::Image { width: image.width(), height: image.height(), rgba: image.into_raw() })
+        }
+        ClipKind::Files => Some(CapturedClip::Files(clip.file_paths())),
+    }
+}
+
+// What `copy` puts on the clipboard, or what is missing to do so
+pub fn copy_source(conn: &Connection, clip: Clip, original: bool) -> Result<CapturedClip, String> {
+    if original {
+        // The rich formats were captured alongside the cleaned link, so only the text goes
+        let text = clip.original.ok_or(format!("original link of clip {}", clip.id))?;
+        return Ok(CapturedClip::Text { text, formats: Vec::new(), selection: clip.selection });
+    }
+    captured_clip(conn, &clip).ok_or(format!("image of clip {}", clip.id))
+}
+
+// X11 and Wayland drop the value when its owner exits. The running daemon can keep serving
+// it; without one, on Linux a detached copy of this process does until something else is copied.
+fn copy_clip(clip: &CapturedClip, id: i64, original: bool, foreground: bool) -> CliResult {
+    let (settings, _) = Settings::load();
+    let clipboard_error = |e: Box<dyn Error>| CliError::Clipboard(e.to_string());
+    if cfg!(target_os = "linux") && foreground {
+        // The parent waits for one line on stdout: COPY_SERVED, or else the error
+        let served = clipboard::serve_until_replaced(clip, settings.clipboard_backend, || println!("{}", COPY_SERVED));
+        if let Err(e) = &served {
+            println!("{}", e);
+        }
+        return served.map_err(clipboard_error);
+    }
+    #[cfg(unix)]
+    if crate::ipc::request("copy", serde_json::json!({ "id": id, "original": original })).is_ok() {
+        return Ok(());
+    }
+    if cfg!(target_os = "linux") {
+        let mut command = Command::new(std::env::current_exe()?);
+        command.args(["copy", &id.to_string(), "--foreground"]);
+        if original {
+            command.arg("--original");
+        }
+        let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+        let mut report = String::new();
+        BufReader::new(child.stdout.take().expect("stdout is piped")).read_line(&mut report)?;
+        return match report.trim_end() {
+            COPY_SERVED => Ok(()),
+            "" => Err(CliError::Clipboard(format!("copy process exited ({})", child.wait()?))),
+            error => {
+                let _ = child.wait();
+                Err(CliError::Clipboard(error.to_string()))
+            }
+        };
+    }
+
+    clipboard::use_backend(clipboard::create_backend(settings.clipboard_backend).map_err(clipboard_error)?);
+    clipboard::copy_captured(clip).map_err(clipboard_error)
+}
+
+fn set_pinned(conn: &Connection, ids: &[i64], pinned: bool) -> CliResult {
+    for id in ids {
+        load_clip(conn, *id)?;
+        db::set_pinned(conn, *id, pinned)?;
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize>(value: &T) -> CliResult {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+// One line per clip: ID, time, flags and the first line of the content
+fn print_clips(conn: &Connection, clips: Vec<Clip>, json: bool) -> CliResult {
+    let mut tags = db::load_clip_tags(conn)?;
+    if json {
+        let output: Vec<ClipInfo> = clips
+            .into_iter()
+            .map(|clip| {
+                let clip_tags = tags.remove(&clip.id).unwrap_or_default();
+                ClipInfo::new(clip, clip_tags)
+            })
+            .collect();
+        return print_json(&output);
+    }
+    for clip in clips {
+        let flags = match (clip.pinned, clip.kind) {
+            (true, ClipKind::Text) => "pinned".to_string(),
+            (true, kind) => format!("pinned {}", kind.as_str()),
+            (false, ClipKind::Text) => String::new(),
+            (false, kind) => kind.as_str().to_string(),
+        };
+        let preview = clip.title.clone().unwrap_or_else(|| clip.content.lines().next().unwrap_or_default().to_string());
+        println!("{}\t{}\t{}\t{}", clip.id, format_timestamp(clip.timestamp), flags, preview);
+    }
+    Ok(())
+}
+
+fn print_tags(conn: &Connection, id: i64, json: bool) -> CliResult {
+    let tags = clip_tags(conn, id)?;
+    if json {
+        return print_json(&serde_json::json!({ "id": id, "tags": tags }));
+    }
+    println!("{}", tags.join(", "));
+    Ok(())
+}
+
+fn print_pause_state(state: PauseState, json: bool) -> CliResult {
+    if json {
+        return print_json(&pause_status(state));
+    }
+    println!("{}", state.label(chrono::Utc::now().timestamp()));
+    Ok(())
+}
+
+// Change the recording state through the running daemon, so its monitor and tray icon follow
+// right away. Without a daemon there is only the database to update.
+pub fn set_pause_state(conn: &Connection, state: PauseState) -> Result<(), Box<dyn Error>> {
+    #[cfg(unix)]
+    {
+        let now = chrono::Utc::now().timestamp();
+        let sent = match state {
+            PauseState::Recording => crate::ipc::request("resume", serde_json::Value::Null),
+            PauseState::Paused => crate::ipc::request("pause", serde_json::json!({})),
+            PauseState::Incognito { until } => {
+                let minutes = ((until - now + 59) / 60).max(1);
+                crate::ipc::request("pause", serde_json::json!({ "minutes": minutes }))
+            }
+        };
+        if sent.is_ok() {
+            return Ok(());
+        }
+    }
+    db::save_pause_state(conn, state)?;
+    Ok(())
+}
+
+// Recording state as `status --json` and the IPC API report it
+pub fn pause_status(state: PauseState) -> serde_json::Value {
+    let until = match state {
+        PauseState::Incognito { until } => Some(until),
+        _ => None,
+    };
+    serde_json::json!({
+        "recording": state.is_recording(),
+        "paused_until": until,
+        "label": state.label(chrono::Utc::now().timestamp()),
+    })
+}
@@ -0,0 +1,713 @@
+// The clipboard implementations the monitor and the copy buttons can work through
+use std::{
+    env,
+    error::Error,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex, mpsc::Sender},
+};
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+use copypasta::{ClipboardContext, ClipboardProvider};
+use serde::{Deserialize, Serialize};
+use crate::models::{ClipFormat, ClipOrigin, Selection};
+use crate::utils::files::{parse_uri_list, to_uri_list};
+use crate::utils::images::{decode_png, encode_rgba_png};
+use super::{RICH_FORMATS, Wake};
+
+pub type BackendResult<T> = Result<T, Box<dyn Error>>;
+
+// Raw RGBA8 pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+// Reading, writing and watching one clipboard. Getters return Ok(None) when the
+// clipboard holds nothing of that kind; the defaults describe a text-only clipboard.
+pub trait ClipboardBackend: Send {
+    fn name(&self) -> &'static str;
+
+    fn get_text(&mut self, selection: Selection) -> BackendResult<Option<String>>;
+
+    fn set_text(&mut self, text: &str) -> BackendResult<()>;
+
+    // Take the PRIMARY selection with the given text
+    fn set_primary(&mut self, _text: &str) -> BackendResult<()> {
+        Err(format!("the {} backend can't set the PRIMARY selection", self.name()).into())
+    }
+
+    fn get_image(&mut self) -> BackendResult<Option<ImageData>> {
+        Ok(None)
+    }
+
+    fn set_image(&mut self, _image: &ImageData) -> BackendResult<()> {
+        Err(format!("the {} backend can't copy images", self.name()).into())
+    }
+
+    fn get_files(&mut self) -> BackendResult<Option<Vec<PathBuf>>> {
+        Ok(None)
+    }
+
+    fn set_files(&mut self, _paths: &[PathBuf]) -> BackendResult<()> {
+        Err(format!("the {} backend can't copy file lists", self.name()).into())
+    }
+
+    // The RICH_FORMATS offered next to the current text
+    fn get_formats(&mut self) -> Vec<ClipFormat> {
+        Vec::new()
+    }
+
+    // Every MIME type / target CLIPBOARD is currently offered as
+    fn offered_types(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // The application owning the selection, only known on X11
+    fn origin(&mut self, selection: Selection) -> Option<ClipOrigin> {
+        #[cfg(target_os = "linux")]
+        return super::origin::selection_origin(selection);
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = selection;
+            None
+        }
+    }
+
+    // Plain text with its captured formats, backends without HTML support only set the text
+    fn set_formatted(&mut self, plain: &str, _formats: &[ClipFormat]) -> BackendResult<()> {
+        self.set_text(plain)
+    }
+
+    // Start sending `Wake::Changed` whenever a selection changes. Returns the mechanism
+    // used, or None when the monitor has to poll.
+    fn watch(&mut self, watch_primary: bool, wake: Sender<Wake>) -> Option<&'static str> {
+        #[cfg(target_os = "linux")]
+        return super::watch::spawn_watcher(watch_primary, wake);
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (watch_primary, wake);
+            None
+        }
+    }
+}
+
+// Which backend to use, picked in the settings
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackendKind {
+    // Text, images, file lists and rich formats
+    #[default]
+    Arboard,
+    // Text only
+    Copypasta,
+    // xclip, xsel or wl-copy/wl-paste, for setups where the libraries misbehave
+    Command,
+}
+
+impl BackendKind {
+    pub const ALL: [BackendKind; 3] = [BackendKind::Arboard, BackendKind::Copypasta, BackendKind::Command];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BackendKind::Arboard => "arboard",
+            BackendKind::Copypasta => "copypasta (text only)",
+            BackendKind::Command => "xclip / xsel / wl-clipboard",
+        }
+    }
+}
+
+pub fn create_backend(kind: BackendKind) -> BackendResult<Box<dyn ClipboardBackend>> {
+    Ok(match kind {
+        BackendKind::Arboard => Box::new(ArboardBackend::new()?),
+        BackendKind::Copypasta => Box::new(CopypastaBackend::new()?),
+        BackendKind::Command => Box::new(CommandBackend::detect()?),
+    })
+}
+
+pub struct ArboardBackend {
+    clipboard: arboard::Clipboard,
+    // arboard doesn't expose TARGETS, rich formats and PRIMARY are read directly
+    #[cfg(target_os = "linux")]
+    x11: Option<x11_clipboard::Clipboard>,
+}
+
+impl ArboardBackend {
+    pub fn new() -> BackendResult<Self> {
+        Ok(Self {
+            clipboard: arboard::Clipboard::new()?,
+            #[cfg(target_os = "linux")]
+            x11: x11_clipboard::Clipboard::new().ok(),
+        })
+    }
+}
+
+// arboard reports an empty or differently typed clipboard as an error
+fn available<T>(result: Result<T, arboard::Error>) -> BackendResult<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+impl ClipboardBackend for ArboardBackend {
+    fn name(&self) -> &'static str {
+        "arboard"
+    }
+
+    fn get_text(&mut self, selection: Selection) -> BackendResult<Option<String>> {
+        match selection {
+            Selection::Clipboard => available(self.clipboard.get_text()),
+            #[cfg(target_os = "linux")]
+            Selection::Primary => Ok(self.x11.as_ref().and_then(read_primary)),
+            #[cfg(not(target_os = "linux"))]
+            Selection::Primary => Ok(None),
+        }
+    }
+
+    fn set_text(&mut self, text: &str) -> BackendResult<()> {
+        Ok(self.clipboard.set_text(text)?)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_primary(&mut self, text: &str) -> BackendResult<()> {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+        Ok(self.clipboard.set().clipboard(LinuxClipboardKind::Primary).text(text)?)
+    }
+
+    fn get_image(&mut self) -> BackendResult<Option<ImageData>> {
+        Ok(available(self.clipboard.get_image())?.map(|image| ImageData {
+            width: image.width as u32,
+            height: image.height as u32,
+            rgba: image.bytes.into_owned(),
+        }))
+    }
+
+    fn set_image(&mut self, image: &ImageData) -> BackendResult<()> {
+        Ok(self.clipboard.set_image(arboard::ImageData {
+            width: image.width as usize,
+            height: image.height as usize,
+            bytes: image.rgba.as_slice().into(),
+        })?)
+    }
+
+    fn get_files(&mut self) -> BackendResult<Option<Vec<PathBuf>>> {
+        available(self.clipboard.get().file_list())
+    }
+
+    fn set_files(&mut self, paths: &[PathBuf]) -> BackendResult<()> {
+        Ok(self.clipboard.set().file_list(paths)?)
+    }
+
+    fn get_formats(&mut self) -> Vec<ClipFormat> {
+        #[cfg(target_os = "linux")]
+        return self.x11.as_ref().map(read_rich_formats).unwrap_or_default();
+        // Other platforms only expose HTML through arboard
+        #[cfg(not(target_os = "linux"))]
+        self.clipboard
+            .get()
+            .html()
+            .ok()
+            .filter(|html| !html.is_empty())
+            .map(|html| vec![ClipFormat::new("text/html", html.into_bytes())])
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn offered_types(&mut self) -> Vec<String> {
+        self.x11.as_ref().map(read_targets).unwrap_or_default()
+    }
+
+    fn set_formatted(&mut self, plain: &str, formats: &[ClipFormat]) -> BackendResult<()> {
+        match formats.iter().find(|f| f.mime == "text/html") {
+            Some(html) => Ok(self.clipboard.set().html(html.as_text(), Some(plain.to_string()))?),
+            None => self.set_text(plain),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_primary(x11: &x11_clipboard::Clipboard) -> Option<String> {
+    let atoms = &x11.getter.atoms;
+    let data = x11
+        .load(atoms.primary, atoms.utf8_string, atoms.property, Duration::from_millis(200))
+        .ok()?;
+    String::from_utf8(data).ok()
+}
+
+// Names of the targets the CLIPBOARD owner offers
+#[cfg(target_os = "linux")]
+fn read_targets(x11: &x11_clipboard::Clipboard) -> Vec<String> {
+    use x11rb::protocol::xproto::ConnectionExt as _;
+
+    let atoms = &x11.getter.atoms;
+    let Ok(targets) = x11.load(atoms.clipboard, atoms.targets, atoms.property, Duration::from_millis(200)) else {
+        return Vec::new();
+    };
+    targets
+        .chunks_exact(4)
+        .map(|atom| u32::from_ne_bytes([atom[0], atom[1], atom[2], atom[3]]))
+        .filter_map(|atom| x11.getter.connection.get_atom_name(atom).ok()?.reply().ok())
+        .map(|reply| String::from_utf8_lossy(&reply.name).into_owned())
+        .collect()
+}
+
+// Ask the CLIPBOARD owner which targets it offers and read the rich ones
+#[cfg(target_os = "linux")]
+fn read_rich_formats(x11: &x11_clipboard::Clipboard) -> Vec<ClipFormat> {
+    let timeout = Duration::from_millis(200);
+    let atoms = &x11.getter.atoms;
+    let Ok(targets) = x11.load(atoms.clipboard, atoms.targets, atoms.property, timeout) else {
+        return Vec::new();
+    };
+    let offered: Vec<u32> = targets
+        .chunks_exact(4)
+        .map(|atom| u32::from_ne_bytes([atom[0], atom[1], atom[2], atom[3]]))
+        .collect();
+
+    RICH_FORMATS
+        .iter()
+        .filter_map(|mime| {
+            let atom = x11.getter.get_atom(mime).ok()?;
+            if !offered.contains(&atom) {
+                return None;
+            }
+            let data = x11.load(atoms.clipboard, atom, atoms.property, timeout).ok()?;
+            (!data.is_empty()).then(|| ClipFormat::new(mime, data))
+        })
+        .collect()
+}
+
+pub struct CopypastaBackend {
+    ctx: ClipboardContext,
+    #[cfg(target_os = "linux")]
+    primary: Option<copypasta::x11_clipboard::X11ClipboardContext<copypasta::x11_clipboard::Primary>>,
+}
+
+impl CopypastaBackend {
+    pub fn new() -> BackendResult<Self> {
+        Ok(Self {
+            ctx: ClipboardContext::new()?,
+            #[cfg(target_os = "linux")]
+            primary: copypasta::x11_clipboard::X11ClipboardContext::new().ok(),
+        })
+    }
+}
+
+impl ClipboardBackend for CopypastaBackend {
+    fn name(&self) -> &'static str {
+        "copypasta"
+    }
+
+    // copypasta can't tell an empty clipboard from a failed read, both count as empty
+    fn get_text(&mut self, selection: Selection) -> BackendResult<Option<String>> {
+        Ok(match selection {
+            Selection::Clipboard => self.ctx.get_contents().ok(),
+            #[cfg(target_os = "linux")]
+            Selection::Primary => self.primary.as_mut().and_then(|primary| primary.get_contents().ok()),
+            #[cfg(not(target_os = "linux"))]
+            Selection::Primary => None,
+        })
+    }
+
+    fn set_text(&mut self, text: &str) -> BackendResult<()> {
+        self.ctx.set_contents(text.to_string())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_primary(&mut self, text: &str) -> BackendResult<()> {
+        match self.primary.as_mut() {
+            Some(primary) => primary.set_contents(text.to_string()),
+            None => Err("no X11 connection for the PRIMARY selection".into()),
+        }
+    }
+}
+
+// The command line tool the command backend drives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardCommand {
+    WlClipboard,
+    Xclip,
+    Xsel,
+}
+
+impl ClipboardCommand {
+    // wl-clipboard on Wayland, otherwise whichever X11 tool is installed
+    pub fn detect() -> Option<Self> {
+        let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+        if wayland && command_exists("wl-paste") && command_exists("wl-copy") {
+            Some(ClipboardCommand::WlClipboard)
+        } else if command_exists("xclip") {
+            Some(ClipboardCommand::Xclip)
+        } else if command_exists("xsel") {
+            Some(ClipboardCommand::Xsel)
+        } else {
+            None
+        }
+    }
+
+    // Program and arguments printing the selection, as the given MIME type if the tool supports it
+    fn read_args(self, selection: Selection, mime: Option<&str>) -> Option<(&'static str, Vec<String>)> {
+        let primary = selection == Selection::Primary;
+        let mut args = Vec::new();
+        let program = match self {
+            ClipboardCommand::WlClipboard => {
+                if primary {
+                    args.push("--primary".to_string());
+                }
+                args.push("--no-newline".to_string());
+                args.extend(["--type".to_string(), mime.unwrap_or("text").to_string()]);
+                "wl-paste"
+            }
+            ClipboardCommand::Xclip => {
+                args.extend(["-selection".to_string(), selection.as_str().to_string()]);
+                if let Some(mime) = mime {
+                    args.extend(["-t".to_string(), mime.to_string()]);
+                }
+                args.push("-o".to_string());
+                "xclip"
+            }
+            ClipboardCommand::Xsel => {
+                if mime.is_some() {
+                    return None;
+                }
+                args.push(if primary { "--primary" } else { "--clipboard" }.to_string());
+                args.push("--output".to_string());
+                "xsel"
+            }
+        };
+        Some((program, args))
+    }
+
+    // Program and arguments reading new selection contents from stdin
+    fn write_args(self, selection: Selection, mime: Option<&str>) -> Option<(&'static str, Vec<String>)> {
+        let primary = selection == Selection::Primary;
+        let mut args = Vec::new();
+        let program = match self {
+            ClipboardCommand::WlClipboard => {
+                if primary {
+                    args.push("--primary".to_string());
+                }
+                if let Some(mime) = mime {
+                    args.extend(["--type".to_string(), mime.to_string()]);
+                }
+                "wl-copy"
+            }
+            ClipboardCommand::Xclip => {
+                args.extend(["-selection".to_string(), selection.as_str().to_string()]);
+                if let Some(mime) = mime {
+                    args.extend(["-t".to_string(), mime.to_string()]);
+                }
+                args.push("-i".to_string());
+                "xclip"
+            }
+            ClipboardCommand::Xsel => {
+                if mime.is_some() {
+                    return None;
+                }
+                args.push(if primary { "--primary" } else { "--clipboard" }.to_string());
+                args.push("--input".to_string());
+                "xsel"
+            }
+        };
+        Some((program, args))
+    }
+
+    // Program and arguments listing the MIME types CLIPBOARD is offered as
+    fn list_args(self) -> Option<(&'static str, Vec<String>)> {
+        match self {
+            ClipboardCommand::WlClipboard => Some(("wl-paste", vec!["--list-types".to_string()])),
+            ClipboardCommand::Xclip => Some((
+                "xclip",
+                ["-selection", "clipboard", "-t", "TARGETS", "-o"].map(String::from).to_vec(),
+            )),
+            ClipboardCommand::Xsel => None,
+        }
+    }
+}
+
+fn command_exists(name: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+        .unwrap_or(false)
+}
+
+// Clipboard access through external commands, one process per read or write
+pub struct CommandBackend {
+    command: ClipboardCommand,
+}
+
+impl CommandBackend {
+    pub fn new(command: ClipboardCommand) -> Self {
+        Self { command }
+    }
+
+    pub fn detect() -> BackendResult<Self> {
+        ClipboardCommand::detect()
+            .map(Self::new)
+            .ok_or_else(|| "none of wl-clipboard, xclip or xsel is installed".into())
+    }
+
+    // The tools exit with an error when the clipboard doesn't hold the requested type
+    fn read(&self, selection: Selection, mime: Option<&str>) -> BackendResult<Option<Vec<u8>>> {
+        let Some((program, args)) = self.command.read_args(selection, mime) else {
+            return Ok(None);
+        };
+        let output = Command::new(program)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()?;
+        Ok(output.status.success().then_some(output.stdout))
+    }
+
+    fn write(&self, selection: Selection, mime: Option<&str>, data: &[u8]) -> BackendResult<()> {
+        let Some((program, args)) = self.command.write_args(selection, mime) else {
+            return Err(format!("{} can only copy plain text", self.name()).into());
+        };
+        // The tools fork to keep serving the selection, stdout must not be a pipe we wait on
+        let mut child = Command::new(program)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        child.stdin.take().ok_or("no stdin for the clipboard command")?.write_all(data)?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(format!("{} exited with {}", program, status).into());
+        }
+        Ok(())
+    }
+
+    fn list_types(&self) -> Vec<String> {
+        let Some((program, args)) = self.command.list_args() else {
+            return Vec::new();
+        };
+        Command::new(program)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).lines().map(|line| line.trim().to_string()).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl ClipboardBackend for CommandBackend {
+    fn name(&self) -> &'static str {
+        match self.command {
+            ClipboardCommand::WlClipboard => "wl-clipboard",
+            ClipboardCommand::Xclip => "xclip",
+            ClipboardCommand::Xsel => "xsel",
+        }
+    }
+
+    fn get_text(&mut self, selection: Selection) -> BackendResult<Option<String>> {
+        Ok(self.read(selection, None)?.map(|data| String::from_utf8_lossy(&data).into_owned()))
+    }
+
+    fn set_text(&mut self, text: &str) -> BackendResult<()> {
+        self.write(Selection::Clipboard, None, text.as_bytes())
+    }
+
+    fn set_primary(&mut self, text: &str) -> BackendResult<()> {
+        self.write(Selection::Primary, None, text.as_bytes())
+    }
+
+    fn get_image(&mut self) -> BackendResult<Option<ImageData>> {
+        if !self.list_types().iter().any(|mime| mime == "image/png") {
+            return Ok(None);
+        }
+        let Some(png) = self.read(Selection::Clipboard, Some("image/png"))? else {
+            return Ok(None);
+        };
+        let image = decode_png(&png)?;
+        Ok(Some(ImageData { width: image.width(), height: image.height(), rgba: image.into_raw() }))
+    }
+
+    fn set_image(&mut self, image: &ImageData) -> BackendResult<()> {
+        let png = encode_rgba_png(image.width, image.height, &image.rgba)?;
+        self.write(Selection::Clipboard, Some("image/png"), &png)
+    }
+
+    fn get_files(&mut self) -> BackendResult<Option<Vec<PathBuf>>> {
+        if !self.list_types().iter().any(|mime| mime == "text/uri-list") {
+            return Ok(None);
+        }
+        Ok(self
+            .read(Selection::Clipboard, Some("text/uri-list"))?
+            .map(|list| parse_uri_list(&String::from_utf8_lossy(&list))))
+    }
+
+    fn set_files(&mut self, paths: &[PathBuf]) -> BackendResult<()> {
+        self.write(Selection::Clipboard, Some("text/uri-list"), to_uri_list(paths).as_bytes())
+    }
+
+    fn get_formats(&mut self) -> Vec<ClipFormat> {
+        let offered = self.list_types();
+        RICH_FORMATS
+            .iter()
+            .filter(|mime| offered.iter().any(|offered| offered == *mime))
+            .filter_map(|mime| {
+                let data = self.read(Selection::Clipboard, Some(mime)).ok()??;
+                (!data.is_empty()).then(|| ClipFormat::new(mime, data))
+            })
+            .collect()
+    }
+
+    fn offered_types(&mut self) -> Vec<String> {
+        self.list_types()
+    }
+}
+
+// What the mock clipboard currently holds
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockContent {
+    Text { text: String, formats: Vec<ClipFormat> },
+    Image(ImageData),
+    Files(Vec<PathBuf>),
+}
+
+#[derive(Default)]
+struct MockState {
+    clipboard: Option<MockContent>,
+    primary: Option<String>,
+    origin: Option<ClipOrigin>,
+    wake: Option<Sender<Wake>>,
+}
+
+// In-memory clipboard for driving the monitor without a display. Clones share the
+// same contents, so a test keeps one handle to play the other applications.
+#[derive(Clone, Default)]
+pub struct MockBackend {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Simulate another application taking the clipboard
+    pub fn copy(&self, content: MockContent) {
+        self.update(|state| state.clipboard = Some(content));
+    }
+
+    pub fn copy_text(&self, text: &str) {
+        self.copy(MockContent::Text { text: text.to_string(), formats: Vec::new() });
+    }
+
+    // Simulate selecting text with the mouse
+    pub fn select(&self, text: &str) {
+        self.update(|state| state.primary = Some(text.to_string()));
+    }
+
+    // The application the next copies appear to come from
+    pub fn set_origin(&self, origin: Option<ClipOrigin>) {
+        self.lock().origin = origin;
+    }
+
+    pub fn clear(&self) {
+        self.update(|state| state.clipboard = None);
+    }
+
+    pub fn contents(&self) -> Option<MockContent> {
+        self.lock().clipboard.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Change the contents and wake a watching monitor, like a real selection change
+    fn update(&self, change: impl FnOnce(&mut MockState)) {
+        let mut state = self.lock();
+        change(&mut state);
+        if let Some(wake) = &state.wake {
+            let _ = wake.send(Wake::Changed);
+        }
+    }
+}
+
+impl ClipboardBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn get_text(&mut self, selection: Selection) -> BackendResult<Option<String>> {
+        let state = self.lock();
+        Ok(match selection {
+            Selection::Clipboard => match &state.clipboard {
+                Some(MockContent::Text { text, .. }) => Some(text.clone()),
+                _ => None,
+            },
+            Selection::Primary => state.primary.clone(),
+        })
+    }
+
+    fn set_text(&mut self, text: &str) -> BackendResult<()> {
+        self.copy_text(text);
+        Ok(())
+    }
+
+    fn set_primary(&mut self, text: &str) -> BackendResult<()> {
+        self.select(text);
+        Ok(())
+    }
+
+    fn get_image(&mut self) -> BackendResult<Option<ImageData>> {
+        Ok(match &self.lock().clipboard {
+            Some(MockContent::Image(image)) => Some(image.clone()),
+            _ => None,
+        })
+    }
+
+    fn set_image(&mut self, image: &ImageData) -> BackendResult<()> {
+        self.copy(MockContent::Image(image.clone()));
+        Ok(())
+    }
+
+    fn get_files(&mut self) -> BackendResult<Option<Vec<PathBuf>>> {
+        Ok(match &self.lock().clipboard {
+            Some(MockContent::Files(paths)) => Some(paths.clone()),
+            _ => None,
+        })
+    }
+
+    fn set_files(&mut self, paths: &[PathBuf]) -> BackendResult<()> {
+        self.copy(MockContent::Files(paths.to_vec()));
+        Ok(())
+    }
+
+    fn get_formats(&mut self) -> Vec<ClipFormat> {
+        match &self.lock().clipboard {
+            Some(MockContent::Text { formats, .. }) => formats.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Formats double as the offered types, so a test can copy with a password manager hint
+    fn offered_types(&mut self) -> Vec<String> {
+        self.get_formats().into_iter().map(|format| format.mime).collect()
+    }
+
+    fn origin(&mut self, _selection: Selection) -> Option<ClipOrigin> {
+        self.lock().origin.clone()
+    }
+
+    fn set_formatted(&mut self, plain: &str, formats: &[ClipFormat]) -> BackendResult<()> {
+        self.copy(MockContent::Text { text: plain.to_string(), formats: formats.to_vec() });
+        Ok(())
+    }
+
+    fn watch(&mut self, _watch_primary: bool, wake: Sender<Wake>) -> Option<&'static str> {
+        self.lock().wake = Some(wake);
+        Some("mock")
+    }
+}
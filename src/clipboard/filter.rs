@@ -0,0 +1,190 @@
+// Capture filters run on copied text before it is saved. Each stage can rewrite the
+// text or drop it, and the outcome names the stage that decided
+use regex::Regex;
+use serde::{ Deserialize, Serialize };
+
+use crate::models::CaptureLogEntry;
+use crate::utils::urls::UrlCleaning;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct FilterSettings {
+    pub normalize_line_endings: bool, // CRLF and lone CR become LF
+    pub trim_whitespace: bool,
+    pub max_trailing_newlines: Option<usize>,
+    pub ignore_whitespace_only: bool,
+    pub ignore_digits_only: bool,
+    pub min_length: usize, // in characters, counted after the rewriting stages
+    pub max_length: Option<usize>,
+    pub ignore_patterns: Vec<String>, // regexes, a match anywhere drops the clip
+}
+
+impl Default for FilterSettings {
+    fn default() -> Self {
+        Self {
+            normalize_line_endings: false,
+            trim_whitespace: false,
+            max_trailing_newlines: None,
+            ignore_whitespace_only: true,
+            ignore_digits_only: false,
+            min_length: 1,
+            max_length: None,
+            ignore_patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterStage {
+    LineEndings,
+    Trim,
+    TrailingNewlines,
+    CleanUrl,
+    WhitespaceOnly,
+    DigitsOnly,
+    MinLength,
+    MaxLength,
+    Pattern,
+}
+
+impl FilterStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FilterStage::LineEndings => "line-endings",
+            FilterStage::Trim => "trim",
+            FilterStage::TrailingNewlines => "trailing-newlines",
+            FilterStage::CleanUrl => "clean-url",
+            FilterStage::WhitespaceOnly => "whitespace-only",
+            FilterStage::DigitsOnly => "digits-only",
+            FilterStage::MinLength => "min-length",
+            FilterStage::MaxLength => "max-length",
+            FilterStage::Pattern => "pattern",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterOutcome {
+    // The text to save and the stages that rewrote it on the way
+    Accepted { text: String, changed_by: Vec<FilterStage> },
+    Dropped { stage: FilterStage, reason: String },
+}
+
+impl FilterOutcome {
+    // Whether `stage` rewrote an accepted clip
+    pub fn changed(&self, stage: FilterStage) -> bool {
+        matches!(self, FilterOutcome::Accepted { changed_by, .. } if changed_by.contains(&stage))
+    }
+
+    pub fn log_entry(&self, original: &str, timestamp: i64, clip_id: Option<i64>) -> CaptureLogEntry {
+        let (accepted, stage, detail) = match self {
+            FilterOutcome::Accepted { changed_by, .. } if changed_by.is_empty() => {
+                (true, "accepted".to_string(), "passed every stage unchanged".to_string())
+            }
+            FilterOutcome::Accepted { changed_by, .. } => {
+                let stages = changed_by.iter().map(|stage| stage.as_str()).collect::<Vec<_>>();
+                (true, "accepted".to_string(), format!("rewritten by {}", stages.join(", ")))
+            }
+            FilterOutcome::Dropped { stage, reason } => (false, stage.as_str().to_string(), reason.clone()),
+        };
+        CaptureLogEntry {
+            timestamp,
+            accepted,
+            stage,
+            detail,
+            preview: preview(original),
+            clip_id,
+        }
+    }
+}
+
+pub struct CaptureFilter {
+    settings: FilterSettings,
+    urls: UrlCleaning,
+    patterns: Vec<Regex>,
+}
+
+impl CaptureFilter {
+    // Invalid patterns are reported and skipped rather than dropping everything
+    pub fn new(settings: FilterSettings, urls: UrlCleaning) -> Self {
+        let patterns = settings.ignore_patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    eprintln!("Ignoring invalid capture filter pattern {:?}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self { settings, urls, patterns }
+    }
+
+    // Rewriting stages run first so the checks see the text that would be saved
+    pub fn apply(&self, text: &str) -> FilterOutcome {
+        let settings = &self.settings;
+        let mut text = text.to_string();
+        let mut changed_by = Vec::new();
+        let mut rewrite = |stage: FilterStage, text: &mut String, new: String| {
+            if *text != new {
+                *text = new;
+                changed_by.push(stage);
+            }
+        };
+
+        if settings.normalize_line_endings {
+            let normalized = text.replace("\r\n", "\n").replace('\r', "\n");
+            rewrite(FilterStage::LineEndings, &mut text, normalized);
+        }
+        if settings.trim_whitespace {
+            let trimmed = text.trim().to_string();
+            rewrite(FilterStage::Trim, &mut text, trimmed);
+        }
+        if let Some(max) = settings.max_trailing_newlines {
+            let capped = cap_trailing_newlines(&text, max);
+            rewrite(FilterStage::TrailingNewlines, &mut text, capped);
+        }
+        if let Some(cleaned) = self.urls.clean(&text) {
+            rewrite(FilterStage::CleanUrl, &mut text, cleaned);
+        }
+
+        let drop = |stage: FilterStage, reason: String| FilterOutcome::Dropped { stage, reason };
+        if settings.ignore_whitespace_only && text.trim().is_empty() {
+            return drop(FilterStage::WhitespaceOnly, "only whitespace".to_string());
+        }
+        let trimmed = text.trim();
+        if settings.ignore_digits_only && !trimmed.is_empty() && trimmed.chars().all(|c| c.is_ascii_digit()) {
+            return drop(FilterStage::DigitsOnly, "only digits".to_string());
+        }
+        let length = text.chars().count();
+        if length < settings.min_length {
+            return drop(FilterStage::MinLength, format!("{} characters, minimum is {}", length, settings.min_length));
+        }
+        if let Some(max) = settings.max_length
+            && length > max
+        {
+            return drop(FilterStage::MaxLength, format!("{} characters, maximum is {}", length, max));
+        }
+        if let Some(regex) = self.patterns.iter().find(|regex| regex.is_match(&text)) {
+            return drop(FilterStage::Pattern, format!("matches {}", regex.as_str()));
+        }
+
+        FilterOutcome::Accepted { text, changed_by }
+    }
+}
+
+// Keep at most `max` line breaks at the end, counting CRLF as one
+fn cap_trailing_newlines(text: &str, max: usize) -> String {
+    let body = text.trim_end_matches(['\r', '\n']);
+    let tail = &text[body.len()..];
+    let breaks = tail.split_inclusive('\n').take(max).collect::<String>();
+    format!("{}{}", body, breaks)
+}
+
+fn preview(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(80) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line,
+    }
+}
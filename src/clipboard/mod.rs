@@ -0,0 +1,697 @@
+use std::{
+    error::Error,
+    path::PathBuf,
+    sync::{Mutex, mpsc::{self, Receiver, RecvError, RecvTimeoutError, Sender}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::models::{ClipFormat, ClipOrigin, Selection};
+use crate::settings::Settings;
+use crate::utils::host::local_hostname;
+use crate::utils::images::pixel_hash;
+
+pub mod backend;
+pub mod filter;
+#[cfg(target_os = "linux")]
+mod origin;
+#[cfg(target_os = "linux")]
+mod watch;
+
+pub use backend::{BackendKind, ClipboardBackend, ImageData, create_backend};
+
+// Why the monitor loop woke up
+pub enum Wake {
+    // A selection got a new owner (or the old one went away)
+    Changed,
+    // The change watcher died, the monitor falls back to polling
+    WatcherLost,
+    Command(MonitorCommand),
+}
+
+// Sent through the Sender returned by `monitor_clipboard`
+#[derive(Debug, Clone)]
+pub enum MonitorCommand {
+    Stop,
+    // Stop reading the clipboard until resumed
+    Pause,
+    // Start recording again. Whatever was copied while paused is skipped.
+    Resume,
+    // The settings changed. The backend, `start_paused` and `restore` only apply at startup.
+    Reconfigure(MonitorOptions),
+}
+
+// Representations captured alongside the plain text, when the source offers them
+pub const RICH_FORMATS: &[&str] = &[
+    "text/html",
+    "text/rtf",
+    "application/rtf",
+    "text/x-moz-url",
+    "text/x-moz-url-priv",
+    "chromium/x-source-url",
+];
+
+// Targets password managers add to ask clipboard managers not to record the copy
+pub const SECRET_HINTS: &[&str] = &[
+    "x-kde-passwordManagerHint",
+    "org.nspasteboard.ConcealedType",
+    "ExcludeClipboardContentFromMonitorProcessing",
+];
+
+// How long a growing PRIMARY selection must stay unchanged before it is recorded
+const PRIMARY_SETTLE: Duration = Duration::from_millis(1000);
+
+// Mirroring between the X11 selections, so a selection can be pasted with Ctrl+V and a
+// copy with a middle click
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SelectionSync {
+    #[default]
+    Off,
+    PrimaryToClipboard,
+    ClipboardToPrimary,
+    Both,
+}
+
+impl SelectionSync {
+    pub const ALL: [SelectionSync; 4] = [
+        SelectionSync::Off,
+        SelectionSync::PrimaryToClipboard,
+        SelectionSync::ClipboardToPrimary,
+        SelectionSync::Both,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SelectionSync::Off => "Off",
+            SelectionSync::PrimaryToClipboard => "Selection → clipboard",
+            SelectionSync::ClipboardToPrimary => "Clipboard → selection",
+            SelectionSync::Both => "Both ways",
+        }
+    }
+
+    pub fn primary_to_clipboard(&self) -> bool {
+        matches!(self, SelectionSync::PrimaryToClipboard | SelectionSync::Both)
+    }
+
+    pub fn clipboard_to_primary(&self) -> bool {
+        matches!(self, SelectionSync::ClipboardToPrimary | SelectionSync::Both)
+    }
+}
+
+// What the monitor watches, taken from the user's settings
+#[derive(Debug, Clone, Default)]
+pub struct MonitorOptions {
+    pub watch_primary: bool,
+    pub backend: BackendKind,
+    pub start_paused: bool,
+    // Applications (WM_CLASS or process name) whose copies are never recorded
+    pub excluded_apps: Vec<String>,
+    // Clipboard writes closer together than this collapse into the last one
+    pub coalesce_window: Duration,
+    // Drop a value that is cleared again within the coalesce window
+    pub skip_cleared: bool,
+    // Serve the last recorded clip ourselves when its owner exits and the clipboard empties
+    pub keep_alive: bool,
+    // Put on the clipboard at startup if it is empty, without recording it again
+    pub restore: Option<CapturedClip>,
+    pub sync: SelectionSync,
+}
+
+impl MonitorOptions {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            watch_primary: settings.monitor_primary,
+            backend: settings.clipboard_backend,
+            start_paused: false,
+            excluded_apps: settings.excluded_apps.clone(),
+            coalesce_window: Duration::from_millis(settings.coalesce_window_ms),
+            skip_cleared: settings.skip_cleared_copies,
+            keep_alive: settings.keep_clipboard_alive,
+            restore: None,
+            sync: settings.selection_sync,
+        }
+    }
+
+    // PRIMARY is read to record it or to mirror it to the clipboard
+    pub fn reads_primary(&self) -> bool {
+        self.watch_primary || self.sync.primary_to_clipboard()
+    }
+}
+
+// A new clipboard value picked up by the monitor
+#[derive(Debug, Clone)]
+pub enum CapturedClip {
+    Text { text: String, formats: Vec<ClipFormat>, selection: Selection },
+    // Raw RGBA8 pixels
+    Image { width: u32, height: u32, rgba: Vec<u8> },
+    // Files copied in a file manager (text/uri-list)
+    Files(Vec<PathBuf>),
+}
+
+// How often the clipboard is read when no change notifications are available
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// A pending PRIMARY value can keep growing without new owner events, so it is
+// re-read at this rate until it settles
+const PRIMARY_RECHECK: Duration = Duration::from_millis(250);
+
+pub fn monitor_clipboard<F>(
+    options: MonitorOptions,
+    on_change: F,
+) -> Result<(Sender<MonitorCommand>, JoinHandle<()>), Box<dyn Error>>
+where
+    F: Fn(CapturedClip, ClipOrigin, String) + Send + 'static,
+{
+    let backend = create_backend(options.backend)?;
+    monitor_with_backend(backend, options, on_change)
+}
+
+// Run the monitor on an already created backend, e.g. a MockBackend
+pub fn monitor_with_backend<F>(
+    mut backend: Box<dyn ClipboardBackend>,
+    options: MonitorOptions,
+    on_change: F,
+) -> Result<(Sender<MonitorCommand>, JoinHandle<()>), Box<dyn Error>>
+where
+    F: Fn(CapturedClip, ClipOrigin, String) + Send + 'static,
+{
+    let (command_tx, command_rx): (Sender<MonitorCommand>, Receiver<MonitorCommand>) = mpsc::channel();
+    let (wake_tx, wake_rx) = mpsc::channel();
+
+    let watcher = backend.watch(options.reads_primary(), wake_tx.clone());
+    match watcher {
+        Some(name) => println!("Watching the {} clipboard through {} events", backend.name(), name),
+        None => println!("No clipboard change events available, polling {} every {:?}", backend.name(), POLL_INTERVAL),
+    }
+    let mut monitor = Monitor::new(backend, options);
+    let rewatch = wake_tx.clone();
+
+    // A dropped sender leaves the monitor running, only an explicit stop ends it
+    thread::spawn(move || {
+        while let Ok(command) = command_rx.recv() {
+            let stop = matches!(command, MonitorCommand::Stop);
+            if wake_tx.send(Wake::Command(command)).is_err() || stop {
+                break;
+            }
+        }
+    });
+
+    let handle = thread::spawn(move || {
+        let mut events = watcher.is_some();
+        let mut paused = monitor.options.start_paused;
+        if paused {
+            println!("Clipboard monitor paused");
+        }
+        if let Some(clip) = monitor.options.restore.take() {
+            monitor.restore(clip);
+        }
+        loop {
+            if !paused {
+                monitor.check_primary(&on_change);
+                monitor.check_clipboard(&on_change);
+            }
+
+            // Pending values have to be looked at again once they may have settled
+            let settling = [
+                monitor.primary.pending.as_ref().map(|_| PRIMARY_RECHECK),
+                monitor.burst.remaining(Instant::now()),
+            ].into_iter().flatten().min();
+            let timeout = if paused {
+                None
+            } else if settling.is_some() {
+                settling
+            } else if events {
+                None
+            } else {
+                Some(POLL_INTERVAL)
+            };
+            let wake = match timeout {
+                Some(timeout) => match wake_rx.recv_timeout(timeout) {
+                    Err(RecvTimeoutError::Timeout) => Ok(Wake::Changed),
+                    other => other.map_err(|_| RecvError),
+                },
+                None => wake_rx.recv(),
+            };
+            match wake {
+                Ok(Wake::Changed) => {}
+                Ok(Wake::WatcherLost) => {
+                    println!("Clipboard change events lost, polling every {:?}", POLL_INTERVAL);
+                    events = false;
+                }
+                Ok(Wake::Command(MonitorCommand::Pause)) => {
+                    println!("Clipboard monitor paused");
+                    paused = true;
+                }
+                Ok(Wake::Command(MonitorCommand::Resume)) => {
+                    if paused {
+                        println!("Clipboard monitor resumed");
+                        monitor.skip_current();
+                    }
+                    paused = false;
+                }
+                Ok(Wake::Command(MonitorCommand::Reconfigure(options))) => {
+                    println!("Clipboard monitor settings changed");
+                    let read_primary = monitor.options.reads_primary();
+                    monitor.reconfigure(options);
+                    // The watcher only reports changes of the selections it was started for
+                    if events && monitor.options.reads_primary() && !read_primary {
+                        events = monitor.backend.watch(true, rewatch.clone()).is_some();
+                    }
+                }
+                Ok(Wake::Command(MonitorCommand::Stop)) | Err(_) => break,
+            }
+        }
+    });
+    Ok((command_tx, handle))
+}
+
+// Own the clipboard with `clip` until something else is copied, for `clipvault copy`.
+// X11 and Wayland only keep a value while the process that set it is alive. `on_served` runs
+// once the clip is on the clipboard; errors are only returned before that.
+pub fn serve_until_replaced(clip: &CapturedClip, kind: BackendKind, on_served: impl FnOnce()) -> Result<(), Box<dyn Error>> {
+    let mut backend = create_backend(kind)?;
+    let (wake_tx, wake_rx) = mpsc::channel();
+    backend.watch(false, wake_tx);
+    let mut monitor = Monitor::new(backend, MonitorOptions { backend: kind, ..MonitorOptions::default() });
+    monitor.serve(clip)?;
+    on_served();
+    // Change events make it react sooner, the timeout covers backends without them
+    loop {
+        if let Err(RecvTimeoutError::Disconnected) = wake_rx.recv_timeout(POLL_INTERVAL) {
+            thread::sleep(POLL_INTERVAL);
+        }
+        if monitor.read_clipboard().is_some() {
+            return Ok(());
+        }
+    }
+}
+
+// The backend and what was last seen on it
+struct Monitor {
+    backend: Box<dyn ClipboardBackend>,
+    primary: SelectionDebouncer,
+    burst: BurstCoalescer,
+    last_clip: String,
+    last_image_hash: String,
+    last_files: Vec<PathBuf>,
+    // Last recorded CLIPBOARD value, kept to serve it when `keep_alive` is on
+    served: Option<CapturedClip>,
+    options: MonitorOptions,
+}
+
+impl Monitor {
+    fn new(backend: Box<dyn ClipboardBackend>, options: MonitorOptions) -> Self {
+        Self {
+            backend,
+            primary: SelectionDebouncer::new(PRIMARY_SETTLE),
+            burst: BurstCoalescer::new(options.coalesce_window),
+            last_clip: String::new(),
+            last_image_hash: String::new(),
+            last_files: Vec::new(),
+            served: None,
+            options,
+        }
+    }
+
+    fn reconfigure(&mut self, options: MonitorOptions) {
+        if !options.keep_alive {
+            self.served = None;
+        }
+        if !options.reads_primary() {
+            self.primary.pending = None;
+        }
+        self.burst.window = options.coalesce_window;
+        self.options = MonitorOptions {
+            backend: self.options.backend,
+            start_paused: self.options.start_paused,
+            restore: None,
+            ..options
+        };
+    }
+
+    // Take the current contents as already seen, so nothing copied while paused is recorded
+    fn skip_current(&mut self) {
+        let skip = |_: CapturedClip, _: ClipOrigin, _: String| {};
+        self.check_clipboard(&skip);
+        self.burst.pending = None;
+        self.primary.skip(self.backend.get_text(Selection::Primary).ok().flatten());
+    }
+
+    // Decide whether a new value is recorded and find out where it came from. None when
+    // a password manager marked it secret or it came from an excluded application; it
+    // still counts as seen, so it isn't checked again.
+    fn admit(&mut self, selection: Selection) -> Option<ClipOrigin> {
+        if selection == Selection::Clipboard {
+            let offered = self.backend.offered_types();
+            if let Some(hint) = SECRET_HINTS.iter().find(|hint| offered.iter().any(|mime| mime == *hint)) {
+                println!("Skipping clip marked secret ({})", hint);
+                return None;
+            }
+        }
+        let mut origin = self.backend.origin(selection).unwrap_or_default();
+        if let Some(app) = self.options.excluded_apps.iter().find(|app| origin.matches_app(app)) {
+            println!("Skipping clip from excluded application {}", app);
+            return None;
+        }
+        if origin.hostname.is_none() {
+            origin.hostname = local_hostname();
+        }
+        Some(origin)
+    }
+
+    fn check_primary<F: Fn(CapturedClip, ClipOrigin, String)>(&mut self, on_change: &F) {
+        let mirror = self.options.sync.primary_to_clipboard();
+        if !self.options.watch_primary && !mirror {
+            return;
+        }
+        let now = Instant::now();
+        let selected = self.backend.get_text(Selection::Primary).ok().flatten();
+        let finished = self.primary.update(selected, now).or_else(|| self.primary.flush(now));
+        // Selecting text and then copying it would otherwise be recorded twice
+        if let Some(text) = finished.filter(|text| *text != self.last_clip)
+            && let Some(origin) = self.admit(Selection::Primary)
+        {
+            if mirror {
+                self.mirror_to_clipboard(&text);
+            }
+            if self.options.watch_primary {
+                let timestamp = Utc::now().to_rfc3339();
+                let formats = Vec::new();
+                on_change(CapturedClip::Text { text, formats, selection: Selection::Primary }, origin, timestamp);
+            }
+        }
+    }
+
+    // Mirrored values count as already seen on the other selection, so they are neither
+    // recorded a second time nor mirrored back
+    fn mirror_to_clipboard(&mut self, text: &str) {
+        match self.backend.set_text(text) {
+            Ok(()) => {
+                self.last_clip = text.to_string();
+                self.last_image_hash.clear();
+                self.last_files.clear();
+            }
+            Err(e) => eprintln!("Failed to copy the selection to the clipboard: {}", e),
+        }
+    }
+
+    fn mirror_to_primary(&mut self, text: &str) {
+        match self.backend.set_primary(text) {
+            Ok(()) => self.primary.skip(Some(text.to_string())),
+            Err(e) => eprintln!("Failed to copy the clipboard to the selection: {}", e),
+        }
+    }
+
+    fn check_clipboard<F: Fn(CapturedClip, ClipOrigin, String)>(&mut self, on_change: &F) {
+        let now = Instant::now();
+        match self.read_clipboard() {
+            Some(ClipboardChange::New(clip)) => {
+                let origin = self.admit(Selection::Clipboard);
+                self.burst.push(clip, origin, now);
+            }
+            Some(ClipboardChange::Cleared) if self.options.skip_cleared && self.burst.pending.is_some() => {
+                println!("Skipping a clip that was cleared within {:?}", self.burst.window);
+                self.burst.pending = None;
+                // It was never recorded, so copying the same value again later counts
+                self.last_clip.clear();
+                self.last_image_hash.clear();
+                self.last_files.clear();
+            }
+            Some(ClipboardChange::Cleared) if self.burst.pending.is_none() => {
+                if let Some(clip) = self.served.clone() {
+                    println!("Clipboard owner went away, serving the last clip");
+                    if let Err(e) = self.serve(&clip) {
+                        eprintln!("Failed to put the clip back on the clipboard: {}", e);
+                    }
+                }
+            }
+            _ => {}
+        }
+        if let Some(pending) = self.burst.flush(now) {
+            if pending.writes > 1 {
+                println!("Coalesced {} clipboard writes into one clip", pending.writes);
+            }
+            // Secrets and excluded copies are never served back
+            self.served = None;
+            if let Some(origin) = pending.origin {
+                if self.options.keep_alive {
+                    self.served = Some(pending.clip.clone());
+                }
+                if self.options.sync.clipboard_to_primary()
+                    && let CapturedClip::Text { text, .. } = &pending.clip
+                {
+                    let text = text.clone();
+                    self.mirror_to_primary(&text);
+                }
+                on_change(pending.clip, origin, pending.timestamp);
+            }
+        }
+    }
+
+    // Put the last clip from a previous run back, unless something else was copied since
+    fn restore(&mut self, clip: CapturedClip) {
+        if !self.clipboard_empty() {
+            return;
+        }
+        println!("Restoring the last clip to the clipboard");
+        if let Err(e) = self.serve(&clip) {
+            eprintln!("Failed to put the clip back on the clipboard: {}", e);
+            return;
+        }
+        if self.options.keep_alive {
+            self.served = Some(clip);
+        }
+    }
+
+    // Take ownership of the clipboard with a value we recorded. It becomes the last seen
+    // value, so reading it back isn't recorded as a new copy.
+    fn serve(&mut self, clip: &CapturedClip) -> Result<(), Box<dyn Error>> {
+        match clip {
+            CapturedClip::Text { text, formats, .. } => self.backend.set_formatted(text, formats),
+            CapturedClip::Image { width, height, rgba } => {
+                self.backend.set_image(&ImageData { width: *width, height: *height, rgba: rgba.clone() })
+            }
+            CapturedClip::Files(paths) => self.backend.set_files(paths),
+        }?;
+        self.last_clip.clear();
+        self.last_image_hash.clear();
+        self.last_files.clear();
+        match clip {
+            CapturedClip::Text { text, .. } => self.last_clip = text.clone(),
+            CapturedClip::Image { width, height, rgba } => self.last_image_hash = pixel_hash(*width, *height, rgba),
+            CapturedClip::Files(paths) => {
+                self.last_files = paths.clone();
+                self.last_clip = self.backend.get_text(Selection::Clipboard).ok().flatten().unwrap_or_default();
+            }
+        }
+        Ok(())
+    }
+
+    fn clipboard_empty(&mut self) -> bool {
+        let files = matches!(self.backend.get_files(), Ok(Some(files)) if !files.is_empty());
+        let text = matches!(self.backend.get_text(Selection::Clipboard), Ok(Some(text)) if !text.is_empty());
+        !files && !text && matches!(self.backend.get_image(), Ok(None))
+    }
+
+    // Read the clipboard and report what changed since the last read
+    fn read_clipboard(&mut self) -> Option<ClipboardChange> {
+        // File managers also offer the paths as plain text, so check for a file list first
+        if let Ok(Some(files)) = self.backend.get_files() {
+            if files.is_empty() || files == self.last_files {
+                return None;
+            }
+            self.last_files = files.clone();
+            self.last_clip = self.backend.get_text(Selection::Clipboard).ok().flatten().unwrap_or_default();
+            self.last_image_hash.clear();
+            return Some(ClipboardChange::New(CapturedClip::Files(files)));
+        }
+        let text = self.backend.get_text(Selection::Clipboard);
+        if let Ok(Some(current)) = &text
+            && !current.is_empty()
+        {
+            if *current == self.last_clip {
+                return None;
+            }
+            self.last_clip = current.clone();
+            self.last_image_hash.clear();
+            self.last_files.clear();
+            let formats = self.backend.get_formats();
+            return Some(ClipboardChange::New(CapturedClip::Text {
+                text: current.clone(),
+                formats,
+                selection: Selection::Clipboard,
+            }));
+        }
+        match self.backend.get_image() {
+            Ok(Some(image)) => {
+                let hash = pixel_hash(image.width, image.height, &image.rgba);
+                if hash == self.last_image_hash {
+                    return None;
+                }
+                self.last_image_hash = hash;
+                self.last_clip.clear();
+                self.last_files.clear();
+                Some(ClipboardChange::New(CapturedClip::Image { width: image.width, height: image.height, rgba: image.rgba }))
+            }
+            // Errors may be a busy owner rather than an empty clipboard
+            Ok(None) if text.is_ok() => Some(ClipboardChange::Cleared),
+            _ => None,
+        }
+    }
+}
+
+enum ClipboardChange {
+    New(CapturedClip),
+    // Nothing is on the clipboard any more
+    Cleared,
+}
+
+// A clipboard value waiting for its burst to settle. `origin` is None when the value
+// must not be recorded, which still ends the burst.
+struct PendingClip {
+    clip: CapturedClip,
+    origin: Option<ClipOrigin>,
+    timestamp: String,
+    changed: Instant,
+    writes: usize,
+}
+
+// Collapses a burst of clipboard writes into its final value. Every new value restarts
+// the window; the last one is recorded once nothing has changed for the whole window.
+struct BurstCoalescer {
+    pending: Option<PendingClip>,
+    window: Duration,
+}
+
+impl BurstCoalescer {
+    fn new(window: Duration) -> Self {
+        Self { pending: None, window }
+    }
+
+    fn push(&mut self, clip: CapturedClip, origin: Option<ClipOrigin>, now: Instant) {
+        let writes = self.pending.as_ref().map_or(0, |pending| pending.writes) + 1;
+        let timestamp = Utc::now().to_rfc3339();
+        self.pending = Some(PendingClip { clip, origin, timestamp, changed: now, writes });
+    }
+
+    // The pending value once it has been unchanged for the window
+    fn flush(&mut self, now: Instant) -> Option<PendingClip> {
+        self.pending.take_if(|pending| now.duration_since(pending.changed) >= self.window)
+    }
+
+    // Time left until the pending value settles
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.pending.as_ref().map(|pending| self.window.saturating_sub(now.duration_since(pending.changed)))
+    }
+}
+
+// Collapses a PRIMARY selection that grows (or shrinks) while the mouse drags into one
+// value. Each value that extends the pending one replaces it; the pending value is
+// finished once it has settled, or when an unrelated selection starts.
+struct SelectionDebouncer {
+    pending: Option<(String, Instant)>,
+    last_finished: String,
+    settle: Duration,
+}
+
+impl SelectionDebouncer {
+    fn new(settle: Duration) -> Self {
+        Self { pending: None, last_finished: String::new(), settle }
+    }
+
+    // Feed the current selection; returns a finished value if this one replaces it
+    fn update(&mut self, value: Option<String>, now: Instant) -> Option<String> {
+        let value = value.filter(|v| !v.trim().is_empty() && *v != self.last_finished)?;
+        match &self.pending {
+            Some((pending, _)) if *pending == value => None,
+            Some((pending, _)) if value.starts_with(pending.as_str()) || pending.starts_with(value.as_str()) => {
+                self.pending = Some((value, now));
+                None
+            }
+            Some(_) => {
+                let finished = self.pending.replace((value, now)).map(|(v, _)| v);
+                self.finish(finished)
+            }
+            None => {
+                self.pending = Some((value, now));
+                None
+            }
+        }
+    }
+
+    // Drop the pending value and treat `current` as already recorded
+    fn skip(&mut self, current: Option<String>) {
+        self.pending = None;
+        self.last_finished = current.unwrap_or_default();
+    }
+
+    // Finish the pending value once it has been unchanged for the settle time
+    fn flush(&mut self, now: Instant) -> Option<String> {
+        let settled = matches!(&self.pending, Some((_, changed)) if now.duration_since(*changed) >= self.settle);
+        if !settled {
+            return None;
+        }
+        let finished = self.pending.take().map(|(v, _)| v);
+        self.finish(finished)
+    }
+
+    fn finish(&mut self, value: Option<String>) -> Option<String> {
+        if let Some(value) = &value {
+            self.last_finished = value.clone();
+        }
+        value
+    }
+}
+
+// Kept alive for the life of the process so X11/Wayland keep serving what we set
+static CLIPBOARD: Mutex<Option<Box<dyn ClipboardBackend>>> = Mutex::new(None);
+
+// Replace the backend the copy functions below write through
+pub fn use_backend(backend: Box<dyn ClipboardBackend>) {
+    if let Ok(mut guard) = CLIPBOARD.lock() {
+        *guard = Some(backend);
+    }
+}
+
+fn with_clipboard<F>(set: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnOnce(&mut dyn ClipboardBackend) -> Result<(), Box<dyn Error>>,
+{
+    let mut guard = CLIPBOARD.lock().map_err(|_| "clipboard lock poisoned")?;
+    if guard.is_none() {
+        let (settings, _) = Settings::load();
+        *guard = Some(create_backend(settings.clipboard_backend)?);
+    }
+    set(guard.as_deref_mut().expect("clipboard initialized above"))
+}
+
+pub fn copy_text(text: &str) -> Result<(), Box<dyn Error>> {
+    with_clipboard(|clipboard| clipboard.set_text(text))
+}
+
+// Put a clip on the clipboard with its HTML formatting, falling back to plain text
+// when no HTML representation was captured
+pub fn copy_with_formatting(plain: &str, formats: &[ClipFormat]) -> Result<(), Box<dyn Error>> {
+    with_clipboard(|clipboard| clipboard.set_formatted(plain, formats))
+}
+
+// Put a list of files on the clipboard as a proper URI list, so they can be pasted in a file manager
+pub fn copy_file_list(paths: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    with_clipboard(|clipboard| clipboard.set_files(paths))
+}
+
+pub fn copy_image(image: &ImageData) -> Result<(), Box<dyn Error>> {
+    with_clipboard(|clipboard| clipboard.set_image(image))
+}
+
+// Put a stored clip back on the clipboard in whatever form it was captured
+pub fn copy_captured(clip: &CapturedClip) -> Result<(), Box<dyn Error>> {
+    match clip {
+        CapturedClip::Text { text, formats, .. } => copy_with_formatting(text, formats),
+        CapturedClip::Image { width, height, rgba } => {
+            copy_image(&ImageData { width: *width, height: *height, rgba: rgba.clone() })
+        }
+        CapturedClip::Files(paths) => copy_file_list(paths),
+    }
+}
@@ -0,0 +1,98 @@
+// Finding the application that owns an X11 selection
+use std::fs;
+use x11rb::connection::Connection;
+use x11rb::properties::WmClass;
+use x11rb::protocol::res::{ ClientIdMask, ClientIdSpec, ConnectionExt as _ };
+use x11rb::protocol::xproto::{ AtomEnum, ConnectionExt as _, Window };
+use x11rb::rust_connection::RustConnection;
+use crate::models::{ ClipOrigin, Selection };
+
+// Look up the current owner of `selection`. Uses a fresh connection, this only
+// runs once per captured clip.
+pub fn selection_origin(selection: Selection) -> Option<ClipOrigin> {
+    let (conn, screen) = x11rb::connect(None).ok()?;
+    let atom = match selection {
+        Selection::Clipboard => conn.intern_atom(false, b"CLIPBOARD").ok()?.reply().ok()?.atom,
+        Selection::Primary => AtomEnum::PRIMARY.into(),
+    };
+    let owner = conn.get_selection_owner(atom).ok()?.reply().ok()?.owner;
+    if owner == x11rb::NONE {
+        return None;
+    }
+
+    // Toolkits often own selections with a hidden window, the client leader has the app's properties
+    let mut windows = vec![owner];
+    if let Some(leader) = window_property(&conn, owner, "WM_CLIENT_LEADER").filter(|leader| *leader != owner) {
+        windows.push(leader);
+    }
+
+    let app = windows.iter().find_map(|window| wm_class(&conn, *window));
+    let pid = windows
+        .iter()
+        .find_map(|window| window_property(&conn, *window, "_NET_WM_PID"))
+        .or_else(|| client_pid(&conn, owner));
+    let process = pid.and_then(process_name);
+    let hostname = windows.iter().find_map(|window| text_property(&conn, *window, AtomEnum::WM_CLIENT_MACHINE.into()));
+
+    // Hidden owner windows have no title, the active window does if it belongs to the same client
+    let mut title = windows.iter().find_map(|window| window_title(&conn, *window));
+    if title.is_none() {
+        let root = conn.setup().roots[screen].root;
+        title = window_property(&conn, root, "_NET_ACTIVE_WINDOW")
+            .filter(|active| pid.is_none() || window_property(&conn, *active, "_NET_WM_PID") == pid)
+            .and_then(|active| window_title(&conn, active));
+    }
+
+    Some(ClipOrigin {
+        app,
+        process,
+        window_title: title,
+        owner: Some(format!("0x{:x}", owner)),
+        hostname,
+    })
+}
+
+fn window_title(conn: &RustConnection, window: Window) -> Option<String> {
+    let net_wm_name = conn.intern_atom(true, b"_NET_WM_NAME").ok()?.reply().ok()?.atom;
+    [net_wm_name, AtomEnum::WM_NAME.into()]
+        .into_iter()
+        .filter(|atom| *atom != x11rb::NONE)
+        .find_map(|atom| text_property(conn, window, atom))
+}
+
+// A string property such as WM_NAME, in whatever encoding the client used
+fn text_property(conn: &RustConnection, window: Window, property: u32) -> Option<String> {
+    let reply = conn.get_property(false, window, property, AtomEnum::ANY, 0, 1024).ok()?.reply().ok()?;
+    let text = String::from_utf8_lossy(&reply.value).trim_end_matches('\0').trim().to_string();
+    Some(text).filter(|text| !text.is_empty())
+}
+
+fn wm_class(conn: &RustConnection, window: Window) -> Option<String> {
+    let class = WmClass::get(conn, window).ok()?.reply().ok()??;
+    [class.class(), class.instance()]
+        .into_iter()
+        .find(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+}
+
+// First 32-bit value of a window property, e.g. a PID or window id
+fn window_property(conn: &RustConnection, window: Window, name: &str) -> Option<u32> {
+    let atom = conn.intern_atom(true, name.as_bytes()).ok()?.reply().ok()?.atom;
+    if atom == x11rb::NONE {
+        return None;
+    }
+    let reply = conn.get_property(false, window, atom, AtomEnum::ANY, 0, 1).ok()?.reply().ok()?;
+    reply.value32()?.next()
+}
+
+// The X-Resource extension knows the PID of local clients even without _NET_WM_PID
+fn client_pid(conn: &RustConnection, window: Window) -> Option<u32> {
+    let spec = ClientIdSpec { client: window, mask: ClientIdMask::LOCAL_CLIENT_PID };
+    let reply = conn.res_query_client_ids(&[spec]).ok()?.reply().ok()?;
+    reply.ids.iter().find_map(|id| id.value.first().copied())
+}
+
+fn process_name(pid: u32) -> Option<String> {
+    let name = fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    Some(name.trim().to_string()).filter(|name| !name.is_empty())
+}
@@ -0,0 +1,220 @@
+// Selection change notifications from the display server, so the monitor only reads
+// the clipboard when something was actually copied
+use std::{ env, error::Error, sync::mpsc::Sender, thread };
+use super::Wake;
+
+// Start a thread delivering `Wake::Changed` for CLIPBOARD (and PRIMARY when asked).
+// Returns the name of the mechanism in use, or None when neither is available and
+// the caller has to poll.
+pub fn spawn_watcher(watch_primary: bool, wake: Sender<Wake>) -> Option<&'static str> {
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        match wayland::connect(watch_primary) {
+            Ok(watcher) => {
+                let wake = wake.clone();
+                thread::spawn(move || {
+                    if let Err(e) = watcher.run(&wake) {
+                        eprintln!("Wayland selection watcher stopped: {}", e);
+                    }
+                    let _ = wake.send(Wake::WatcherLost);
+                });
+                return Some("wlr-data-control");
+            }
+            // GNOME doesn't offer data-control, XWayland still reports selection changes
+            Err(e) => eprintln!("Wayland data-control unavailable: {}", e),
+        }
+    }
+    if env::var_os("DISPLAY").is_some() {
+        match x11::connect(watch_primary) {
+            Ok(watcher) => {
+                thread::spawn(move || {
+                    if let Err(e) = watcher.run(&wake) {
+                        eprintln!("X11 selection watcher stopped: {}", e);
+                    }
+                    let _ = wake.send(Wake::WatcherLost);
+                });
+                return Some("XFixes");
+            }
+            Err(e) => eprintln!("XFixes selection events unavailable: {}", e),
+        }
+    }
+    None
+}
+
+mod x11 {
+    use super::{ Error, Sender, Wake };
+    use x11rb::connection::Connection;
+    use x11rb::protocol::Event;
+    use x11rb::protocol::xfixes::{ ConnectionExt as _, SelectionEventMask };
+    use x11rb::protocol::xproto::{ AtomEnum, ConnectionExt as _ };
+    use x11rb::rust_connection::RustConnection;
+
+    pub struct Watcher {
+        conn: RustConnection,
+    }
+
+    pub fn connect(watch_primary: bool) -> Result<Watcher, Box<dyn Error>> {
+        let (conn, screen) = x11rb::connect(None)?;
+        conn.xfixes_query_version(5, 0)?.reply()?;
+        let root = conn.setup().roots[screen].root;
+        let clipboard = conn.intern_atom(false, b"CLIPBOARD")?.reply()?.atom;
+
+        let mask = SelectionEventMask::SET_SELECTION_OWNER
+            | SelectionEventMask::SELECTION_WINDOW_DESTROY
+            | SelectionEventMask::SELECTION_CLIENT_CLOSE;
+        conn.xfixes_select_selection_input(root, clipboard, mask)?.check()?;
+        if watch_primary {
+            conn.xfixes_select_selection_input(root, AtomEnum::PRIMARY.into(), mask)?.check()?;
+        }
+        Ok(Watcher { conn })
+    }
+
+    impl Watcher {
+        // Blocks until the connection breaks or the monitor is gone
+        pub fn run(self, wake: &Sender<Wake>) -> Result<(), Box<dyn Error>> {
+            loop {
+                if let Event::XfixesSelectionNotify(_) = self.conn.wait_for_event()?
+                    && wake.send(Wake::Changed).is_err()
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+mod wayland {
+    use super::{ Error, Sender, Wake };
+    use wayland_client::{
+        Connection, Dispatch, EventQueue, Proxy, QueueHandle, event_created_child,
+        globals::{ GlobalListContents, registry_queue_init },
+        protocol::{ wl_registry::WlRegistry, wl_seat::WlSeat },
+    };
+    use wayland_protocols_wlr::data_control::v1::client::{
+        zwlr_data_control_device_v1::{ self, ZwlrDataControlDeviceV1 },
+        zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+        zwlr_data_control_offer_v1::ZwlrDataControlOfferV1,
+    };
+
+    pub struct Watcher {
+        queue: EventQueue<State>,
+        state: State,
+        // Kept alive so the compositor keeps sending selection events
+        _device: ZwlrDataControlDeviceV1,
+    }
+
+    struct State {
+        watch_primary: bool,
+        changed: bool,
+        // Offers are replaced on every selection change, the previous ones must be destroyed
+        clipboard_offer: Option<ZwlrDataControlOfferV1>,
+        primary_offer: Option<ZwlrDataControlOfferV1>,
+    }
+
+    pub fn connect(watch_primary: bool) -> Result<Watcher, Box<dyn Error>> {
+        let conn = Connection::connect_to_env()?;
+        let (globals, mut queue) = registry_queue_init::<State>(&conn)?;
+        let qh = queue.handle();
+        // Version 2 adds the primary selection
+        let manager: ZwlrDataControlManagerV1 = globals.bind(&qh, 1..=2, ())?;
+        let seat: WlSeat = globals.bind(&qh, 1..=1, ())?;
+        let device = manager.get_data_device(&seat, &qh, ());
+
+        let mut state = State {
+            watch_primary,
+            changed: false,
+            clipboard_offer: None,
+            primary_offer: None,
+        };
+        queue.roundtrip(&mut state)?;
+        Ok(Watcher { queue, state, _device: device })
+    }
+
+    impl Watcher {
+        pub fn run(mut self, wake: &Sender<Wake>) -> Result<(), Box<dyn Error>> {
+            loop {
+                self.queue.blocking_dispatch(&mut self.state)?;
+                if std::mem::take(&mut self.state.changed) && wake.send(Wake::Changed).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn replace_offer(slot: &mut Option<ZwlrDataControlOfferV1>, offer: Option<ZwlrDataControlOfferV1>) {
+        if let Some(old) = std::mem::replace(slot, offer) {
+            old.destroy();
+        }
+    }
+
+    impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+        fn event(
+            state: &mut Self,
+            _device: &ZwlrDataControlDeviceV1,
+            event: zwlr_data_control_device_v1::Event,
+            _data: &(),
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {
+            match event {
+                zwlr_data_control_device_v1::Event::Selection { id } => {
+                    replace_offer(&mut state.clipboard_offer, id);
+                    state.changed = true;
+                }
+                zwlr_data_control_device_v1::Event::PrimarySelection { id } => {
+                    replace_offer(&mut state.primary_offer, id);
+                    state.changed |= state.watch_primary;
+                }
+                _ => {}
+            }
+        }
+
+        event_created_child!(State, ZwlrDataControlDeviceV1, [
+            zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ())
+        ]);
+    }
+
+    // The offers' MIME types are read later through the regular clipboard crates
+    impl Dispatch<ZwlrDataControlOfferV1, ()> for State {
+        fn event(
+            _state: &mut Self,
+            _offer: &ZwlrDataControlOfferV1,
+            _event: <ZwlrDataControlOfferV1 as Proxy>::Event,
+            _data: &(),
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {}
+    }
+
+    impl Dispatch<ZwlrDataControlManagerV1, ()> for State {
+        fn event(
+            _state: &mut Self,
+            _manager: &ZwlrDataControlManagerV1,
+            _event: <ZwlrDataControlManagerV1 as Proxy>::Event,
+            _data: &(),
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {}
+    }
+
+    impl Dispatch<WlSeat, ()> for State {
+        fn event(
+            _state: &mut Self,
+            _seat: &WlSeat,
+            _event: <WlSeat as Proxy>::Event,
+            _data: &(),
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {}
+    }
+
+    impl Dispatch<WlRegistry, GlobalListContents> for State {
+        fn event(
+            _state: &mut Self,
+            _registry: &WlRegistry,
+            _event: <WlRegistry as Proxy>::Event,
+            _data: &GlobalListContents,
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {}
+    }
+}
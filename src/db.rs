@@ -1,11 +1,92 @@
-use rusqlite::{Connection, Result, params};
-use std::collections::HashMap;
+use rusqlite::{Connection, OptionalExtension, Result, Row, functions::FunctionFlags, params, params_from_iter, types::Value};
+use crate::models::{
+    CaptureLogEntry, ClipFormat, ClipKind, ClipOrigin, ExportClip, ExportFormat, ExportTag, ImportSummary, PauseState,
+    Selection, VaultExport, VaultStats,
+};
+use crate::models::export::EXPORT_VERSION;
+use crate::utils::images::decode_png;
+use crate::utils::images::ClipImage;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use crate::utils::fuzzy::{FuzzyIndex, clip_text};
+use crate::utils::search::{Filter, KindFilter, SearchQuery};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
 use directories::ProjectDirs;
-use std::path::PathBuf;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const CAPTURE_LOG_LIMIT: i64 = 500;
+
+// The command line keeps stdout for its own output, so it switches this logging off
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+macro_rules! log {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// A clip as loaded from the database:
+/// (id, content, timestamp, pinned, title, note, kind, thumbnail PNG, has rich formats, selection, origin,
+/// original text)
+pub type ClipRow = (
+    i64, String, i64, bool, Option<String>, Option<String>, String, Option<Vec<u8>>, bool, String, Option<ClipOrigin>,
+    Option<String>
+);
+
+// Column list shared by every query that loads clips; rows are mapped with `clip_row`
+const CLIP_SELECT: &str = "
+    SELECT clips.id, clips.content, clips.timestamp, clips.pinned, clips.title, clips.note,
+           clips.kind, clip_images.thumbnail,
+           EXISTS (SELECT 1 FROM clip_formats WHERE clip_formats.clip_id = clips.id),
+           clips.selection,
+           clip_origins.app, clip_origins.process, clip_origins.window_title,
+           clip_origins.owner, clip_origins.hostname,
+           clips.original_content
+    FROM clips
+    LEFT JOIN clip_images ON clip_images.clip_id = clips.id
+    LEFT JOIN clip_origins ON clip_origins.clip_id = clips.id";
+
+fn clip_row(row: &Row) -> Result<ClipRow> {
+    Ok((
+        row.get::<_, i64>(0)?,             // id
+        row.get::<_, String>(1)?,          // content
+        row.get::<_, i64>(2)?,             // timestamp
+        row.get::<_, i64>(3)? != 0,        // pinned (as bool)
+        row.get::<_, Option<String>>(4)?,  // title
+        row.get::<_, Option<String>>(5)?,  // note
+        row.get::<_, String>(6)?,          // kind
+        row.get::<_, Option<Vec<u8>>>(7)?, // thumbnail (image clips only)
+        row.get::<_, bool>(8)?,            // has rich formats
+        row.get::<_, String>(9)?,          // selection
+        clip_origin(row, 10)?,             // where it was copied from
+        row.get::<_, Option<String>>(15)?, // original text when tracking was stripped
+    ))
+}
+
+// The clip_origins columns starting at `first`, None when nothing was recorded
+fn clip_origin(row: &Row, first: usize) -> Result<Option<ClipOrigin>> {
+    let origin = ClipOrigin {
+        app: row.get(first)?,
+        process: row.get(first + 1)?,
+        window_title: row.get(first + 2)?,
+        owner: row.get(first + 3)?,
+        hostname: row.get(first + 4)?,
+    };
+    Ok((!origin.is_empty()).then_some(origin))
+}
 
 /// Update a tag's name and color by tag ID
 pub fn update_tag(conn: &Connection, tag_id: i64, new_name: &str, new_color: &str) -> Result<()> {
-    println!("Updating tag ID {}: name='{}', color='{}'", tag_id, new_name, new_color);
+    log!("Updating tag ID {}: name='{}', color='{}'", tag_id, new_name, new_color);
     conn.execute(
         "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
         params![new_name, new_color, tag_id],
@@ -24,24 +105,47 @@ pub fn get_db_path() -> PathBuf {
 }
 
 pub fn init_db() -> Result<Connection> {
-   println!("Initializing database...");
-    let db_path = get_db_path();
-    println!("Using DB path: {:?}", db_path);
+    init_db_at(get_db_path())
+}
+
+// Open (and create or migrate) the vault at `db_path`; ":memory:" gives a throwaway one
+pub fn init_db_at(db_path: impl AsRef<Path>) -> Result<Connection> {
+   log!("Initializing database...");
+    let db_path = db_path.as_ref();
+    log!("Using DB path: {:?}", db_path);
 
     let conn = Connection::open(db_path)?;
-    println!("Database opened successfully.");
+    log!("Database opened successfully.");
     conn.execute(
         "
         CREATE TABLE IF NOT EXISTS clips (
             id INTEGER PRIMARY KEY,
             content TEXT NOT NULL,
             timestamp INTEGER NOT NULL,
-            pinned INTEGER NOT NULL DEFAULT 0
+            pinned INTEGER NOT NULL DEFAULT 0,
+            sort_rank INTEGER NOT NULL DEFAULT 0,
+            title TEXT DEFAULT NULL,
+            note TEXT DEFAULT NULL,
+            kind TEXT NOT NULL DEFAULT 'text',
+            selection TEXT NOT NULL DEFAULT 'clipboard',
+            original_content TEXT DEFAULT NULL
         )
         ",
         [],
     )?;
 
+    // Older databases predate manual ordering of pinned clips
+    add_column_if_missing(&conn, "clips", "sort_rank", "INTEGER NOT NULL DEFAULT 0")?;
+    // ...and titles/notes on clips
+    add_column_if_missing(&conn, "clips", "title", "TEXT DEFAULT NULL")?;
+    add_column_if_missing(&conn, "clips", "note", "TEXT DEFAULT NULL")?;
+    // ...and non-text clip kinds
+    add_column_if_missing(&conn, "clips", "kind", "TEXT NOT NULL DEFAULT 'text'")?;
+    // ...and the X11 selection a clip came from
+    add_column_if_missing(&conn, "clips", "selection", "TEXT NOT NULL DEFAULT 'clipboard'")?;
+    // ...and the text as copied, before tracking parameters were removed
+    add_column_if_missing(&conn, "clips", "original_content", "TEXT DEFAULT NULL")?;
+
     conn.execute(
         "
         CREATE TABLE IF NOT EXISTS tags (
@@ -66,12 +170,133 @@ pub fn init_db() -> Result<Connection> {
         [],
     )?;
 
-    println!("Tables checked/created.");
+    // Image data for clips of kind 'image'; `pixel_hash` is used for deduplication
+    conn.execute(
+        "
+        CREATE TABLE IF NOT EXISTS clip_images (
+            clip_id INTEGER PRIMARY KEY,
+            png BLOB NOT NULL,
+            thumbnail BLOB NOT NULL,
+            width INTEGER NOT NULL,
+            height INTEGER NOT NULL,
+            pixel_hash TEXT NOT NULL,
+            FOREIGN KEY (clip_id) REFERENCES clips(id) ON DELETE CASCADE
+        )
+        ",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_clip_images_hash ON clip_images (pixel_hash)",
+        [],
+    )?;
+
+    // Extra representations of a clip (text/html, text/rtf, source URL...)
+    conn.execute(
+        "
+        CREATE TABLE IF NOT EXISTS clip_formats (
+            clip_id INTEGER NOT NULL,
+            mime TEXT NOT NULL,
+            data BLOB NOT NULL,
+            PRIMARY KEY (clip_id, mime),
+            FOREIGN KEY (clip_id) REFERENCES clips(id) ON DELETE CASCADE
+        )
+        ",
+        [],
+    )?;
+
+    conn.execute(
+        "
+        CREATE TABLE IF NOT EXISTS clip_sources (
+            clip_id INTEGER NOT NULL,
+            source_clip_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (clip_id, position),
+            FOREIGN KEY (clip_id) REFERENCES clips(id) ON DELETE CASCADE
+        )
+        ",
+        [],
+    )?;
+
+    conn.execute(
+        "
+        CREATE TABLE IF NOT EXISTS clip_origins (
+            clip_id INTEGER PRIMARY KEY,
+            app TEXT,
+            process TEXT,
+            window_title TEXT,
+            owner TEXT,
+            hostname TEXT,
+            FOREIGN KEY (clip_id) REFERENCES clips(id) ON DELETE CASCADE
+        )
+        ",
+        [],
+    )?;
+
+    // Small key/value store for state shared between the tray app, GUI and CLI
+    conn.execute(
+        "
+        CREATE TABLE IF NOT EXISTS app_state (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )
+        ",
+        [],
+    )?;
+
+    // Recent capture filter decisions, trimmed to CAPTURE_LOG_LIMIT rows
+    conn.execute(
+        "
+        CREATE TABLE IF NOT EXISTS capture_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            accepted INTEGER NOT NULL,
+            stage TEXT NOT NULL,
+            detail TEXT NOT NULL,
+            preview TEXT NOT NULL,
+            clip_id INTEGER
+        )
+        ",
+        [],
+    )?;
+
+    log!("Tables checked/created.");
+    register_regexp(&conn)?;
     Ok(conn)
 }
 
+// `text REGEXP pattern` for the regex search mode. SQLite keeps the compiled pattern for the
+// whole statement, and NULL titles or notes never match.
+fn register_regexp(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let regex: Arc<Regex> = ctx.get_or_create_aux(0, |pattern| -> Result<Regex, Box<dyn Error + Send + Sync>> {
+                Ok(Regex::new(pattern.as_str()?)?)
+            })?;
+            let text = ctx.get_raw(1).as_str_or_null()?;
+            Ok(text.is_some_and(|text| regex.is_match(text)))
+        },
+    )
+}
+
+// Add a column to an existing table unless it is already there
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        log!("Adding column {}.{}", table, column);
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, definition))?;
+    }
+    Ok(())
+}
+
 pub fn reset_db(conn: &Connection) -> Result<()> {
-    println!("Resetting database: deleting all entries...");
+    log!("Resetting database: deleting all entries...");
 
     // Disable foreign key checks temporarily to avoid issues with cascade deletes
     conn.execute_batch("PRAGMA foreign_keys = OFF;")?;
@@ -79,6 +304,11 @@ pub fn reset_db(conn: &Connection) -> Result<()> {
     // Delete all data from each table
     conn.execute_batch("
         DELETE FROM clip_tags;
+        DELETE FROM clip_sources;
+        DELETE FROM clip_images;
+        DELETE FROM clip_formats;
+        DELETE FROM clip_origins;
+        DELETE FROM capture_log;
         DELETE FROM clips;
         DELETE FROM tags;
     ")?;
@@ -89,7 +319,7 @@ pub fn reset_db(conn: &Connection) -> Result<()> {
     // Optional: reclaim free space
     conn.execute_batch("VACUUM;")?;
 
-    println!("Database reset: all tables emptied.");
+    log!("Database reset: all tables emptied.");
     let _ = init_db();
     Ok(())
 }
@@ -127,11 +357,11 @@ pub fn load_clip_tags(conn: &Connection) -> Result<HashMap<i64, Vec<String>>> {
 }
 
 pub fn load_tags(conn: &Connection) -> Result<Vec<(i64, String, Option<String>)>> {
-    println!("Getting all tags...");
+    log!("Getting all tags...");
     let mut stmt = match conn.prepare("SELECT id, name, color FROM tags ORDER BY name ASC") {
         Ok(s) => s,
         Err(e) => {
-            println!("Error preparing statement: {}", e);
+            log!("Error preparing statement: {}", e);
             return Err(e);
         }
     };
@@ -146,7 +376,7 @@ pub fn load_tags(conn: &Connection) -> Result<Vec<(i64, String, Option<String>)>
     }) {
         Ok(iter) => iter,
         Err(e) => {
-            println!("Error querying tags: {}", e);
+            log!("Error querying tags: {}", e);
             return Err(e);
         }
     };
@@ -156,26 +386,26 @@ pub fn load_tags(conn: &Connection) -> Result<Vec<(i64, String, Option<String>)>
         match tag_res {
             Ok(tag) => tags.push(tag),
             Err(e) => {
-                println!("Error reading tag row: {}", e);
+                log!("Error reading tag row: {}", e);
                 return Err(e);
             }
         }
     }
 
-    println!("Tags received: {}", tags.len());
+    log!("Tags received: {}", tags.len());
     Ok(tags)
 }
 
 pub fn delete_tag(conn: &Connection, tag_id: i64) -> Result<usize> {
-    println!("Deleting tag with ID: {}", tag_id);
+    log!("Deleting tag with ID: {}", tag_id);
     let result = conn.execute("DELETE FROM tags WHERE id = ?1", params![tag_id]);
     match result {
         Ok(rows) => {
-            println!("Deleted {} row(s).", rows);
+            log!("Deleted {} row(s).", rows);
             Ok(rows)
         }
         Err(e) => {
-            println!("Error deleting tag: {}", e);
+            log!("Error deleting tag: {}", e);
             Err(e)
         }
     }
@@ -187,185 +417,576 @@ pub fn update_tag_color(conn: &Connection, tag_id: i64, color: Option<&str>) ->
     )?;
     Ok(())
 }
-pub fn search_clips(conn: &Connection, query: &str, limit: Option<usize>) -> Result<Vec<(i64, String, i64, bool)>> {
-    println!("Searching clips with query: '{}'", query);
+// Search box syntax, see SearchQuery: words, "phrases", qualifiers like tag: or after:, - and OR
+pub fn search_clips(conn: &Connection, query: &str, limit: Option<usize>) -> Result<Vec<ClipRow>> {
+    log!("Searching clips with query: '{}'", query);
     let limit = limit.unwrap_or(50); // Add default value (e.g., 50)
+    let query = SearchQuery::parse(query);
 
-    let mut stmt = conn.prepare(
-        "SELECT id, content, timestamp, pinned FROM clips
-         WHERE content LIKE ?1
-         ORDER BY pinned DESC, timestamp DESC
-         LIMIT ?2"  // Add LIMIT clause
-    )?;
+    let mut values = Vec::new();
+    let filter = query_sql(&query, &mut values);
+    values.push(Value::Integer(limit as i64));
 
-    let rows = stmt.query_map([format!("%{}%", query), limit.to_string()], |row| {
-        Ok((
-            row.get::<_, i64>(0)?,      // id
-            row.get::<_, String>(1)?,   // content
-            row.get::<_, i64>(2)?,      // timestamp
-            row.get::<_, i64>(3)? != 0, // pinned (as bool)
-        ))
-    })?;
+    let mut stmt = conn.prepare(&format!(
+        "{CLIP_SELECT}
+         WHERE {filter}
+         ORDER BY pinned DESC, sort_rank ASC, timestamp DESC
+         LIMIT ?{}",
+        values.len()
+    ))?;
+
+    let rows = stmt.query_map(params_from_iter(&values), clip_row)?;
 
     let mut clips = Vec::new();
     for clip in rows {
         match &clip {
-            Ok((id, content, timestamp, pinned)) => {
-                println!(
+            Ok((id, content, timestamp, pinned, ..)) => {
+                log!(
                     "Found clip (ID: {}): '{}', timestamp: '{}', pinned: {}",
                     id, content, timestamp, pinned
                 );
             }
-            Err(e) => println!("Error loading a clip row: {}", e),
+            Err(e) => log!("Error loading a clip row: {}", e),
         }
         clips.push(clip?);
     }
 
-    println!("Total clips found: {}", clips.len());
+    log!("Total clips found: {}", clips.len());
+    Ok(clips)
+}
+
+// Regex search mode: `pattern` (flags inlined, see RegexFlags::apply) against content, title and note
+pub fn regex_search_clips(conn: &Connection, pattern: &str, limit: Option<usize>) -> Result<Vec<ClipRow>> {
+    log!("Regex searching clips with pattern: '{}'", pattern);
+    let limit = limit.unwrap_or(50);
+    let mut stmt = conn.prepare(&format!(
+        "{CLIP_SELECT}
+         WHERE clips.content REGEXP ?1 OR clips.title REGEXP ?1 OR clips.note REGEXP ?1
+         ORDER BY pinned DESC, sort_rank ASC, timestamp DESC
+         LIMIT ?2"
+    ))?;
+    let clips = stmt.query_map(params![pattern, limit as i64], clip_row)?.collect::<Result<Vec<_>>>()?;
+    log!("Total clips found: {}", clips.len());
+    Ok(clips)
+}
+
+// WHERE clause for a parsed search against CLIP_SELECT's joins, its ?N values go to `values`
+fn query_sql(query: &SearchQuery, values: &mut Vec<Value>) -> String {
+    if query.is_empty() {
+        return "1".to_string();
+    }
+    let groups: Vec<String> = query.groups
+        .iter()
+        .map(|terms| {
+            if terms.is_empty() {
+                return "1".to_string();
+            }
+            let terms: Vec<String> = terms
+                .iter()
+                .map(|term| {
+                    let sql = filter_sql(&term.filter, values);
+                    // NULL columns (no title, no recorded origin) count as not matching
+                    if term.negated { format!("NOT COALESCE({}, 0)", sql) } else { sql }
+                })
+                .collect();
+            format!("({})", terms.join(" AND "))
+        })
+        .collect();
+    groups.join(" OR ")
+}
+
+fn filter_sql(filter: &Filter, values: &mut Vec<Value>) -> String {
+    let mut bind = |value: Value| {
+        values.push(value);
+        format!("?{}", values.len())
+    };
+    let mut like = |columns: &[&str], text: &str| {
+        let pattern = bind(Value::Text(like_pattern(text)));
+        let matches: Vec<String> = columns
+            .iter()
+            .map(|column| format!("{} LIKE {} ESCAPE '\\'", column, pattern))
+            .collect();
+        format!("({})", matches.join(" OR "))
+    };
+    match filter {
+        Filter::Text(text) => like(&["clips.content", "clips.title", "clips.note"], text),
+        Filter::App(app) => like(&["clip_origins.app", "clip_origins.process"], app),
+        Filter::Window(window) => like(&["clip_origins.window_title"], window),
+        Filter::Host(host) => like(&["clip_origins.hostname"], host),
+        Filter::Tag(name) => format!(
+            "EXISTS (SELECT 1 FROM clip_tags JOIN tags ON tags.id = clip_tags.tag_id
+                     WHERE clip_tags.clip_id = clips.id AND tags.name = {} COLLATE NOCASE)",
+            bind(Value::Text(name.clone()))
+        ),
+        Filter::Pinned(pinned) => format!("clips.pinned = {}", bind(Value::Integer(*pinned as i64))),
+        Filter::Kind(KindFilter::Url) => format!(
+            "(clips.kind = 'text' AND clips.content REGEXP {})",
+            bind(Value::Text(r"(?i)^\s*(https?|ftp)://\S+\s*$".to_string()))
+        ),
+        Filter::Kind(kind) => {
+            let kind = match kind {
+                KindFilter::Image => ClipKind::Image,
+                KindFilter::Files => ClipKind::Files,
+                KindFilter::Text | KindFilter::Url => ClipKind::Text,
+            };
+            format!("clips.kind = {}", bind(Value::Text(kind.as_str().to_string())))
+        }
+        Filter::After(date) => format!("clips.timestamp >= {}", bind(Value::Integer(local_day_start(*date)))),
+        Filter::Before(date) => format!("clips.timestamp < {}", bind(Value::Integer(local_day_start(*date)))),
+        Filter::Length(comparison, length) => {
+            format!("length(clips.content) {} {}", comparison.sql(), bind(Value::Integer(*length)))
+        }
+    }
+}
+
+// `%text%` with LIKE's wildcards taken literally, for `ESCAPE '\'`
+fn like_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+// Timestamp of local midnight starting `date`
+fn local_day_start(date: chrono::NaiveDate) -> i64 {
+    use chrono::{Local, TimeZone};
+
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    Local.from_local_datetime(&midnight).earliest().map_or(midnight.and_utc().timestamp(), |start| start.timestamp())
+}
+
+// Fuzzy search mode: clips ranked by how well their title, content and note match the words
+// of the query as subsequences, the qualifiers filter like in `search_clips`. Each OR alternative
+// is ranked on its own and the lists are merged by position, so all of them show near the top.
+// The in-memory `index` picks up clips added since the last call; edits need `index.clear()`.
+pub fn fuzzy_search_clips(
+    conn: &Connection,
+    index: &mut FuzzyIndex,
+    query: &str,
+    limit: Option<usize>,
+) -> Result<Vec<ClipRow>> {
+    log!("Fuzzy searching clips with query: '{}'", query);
+    let limit = limit.unwrap_or(50);
+    let alternatives = SearchQuery::parse(query).split_text();
+    if alternatives.iter().all(|(words, _)| words.trim().is_empty()) {
+        return search_clips(conn, query, Some(limit));
+    }
+
+    let mut stmt = conn.prepare("SELECT id, title, content, note FROM clips WHERE id > ?1 ORDER BY id")?;
+    let new_clips = stmt
+        .query_map(params![index.last_id()], |row| {
+            let (title, content, note): (Option<String>, String, Option<String>) = (row.get(1)?, row.get(2)?, row.get(3)?);
+            Ok((row.get(0)?, clip_text(title.as_deref(), &content, note.as_deref()).0))
+        })?
+        .collect::<Result<Vec<(i64, String)>>>()?;
+    index.extend(new_clips);
+
+    // Alternatives without words list what SQL lets through in the usual order, the others their
+    // ranked matches narrowed down by the qualifiers
+    let mut lists = Vec::new();
+    for (words, filters) in &alternatives {
+        let ids = if words.trim().is_empty() {
+            filtered_clip_ids(conn, filters, Some(limit))?
+        } else if filters.is_empty() {
+            index.search(words)
+        } else {
+            let allowed: HashSet<i64> = filtered_clip_ids(conn, filters, None)?.into_iter().collect();
+            index.search(words).into_iter().filter(|id| allowed.contains(id)).collect()
+        };
+        lists.push(ids);
+    }
+
+    let mut clips = Vec::new();
+    let mut seen = HashSet::new();
+    let longest = lists.iter().map(Vec::len).max().unwrap_or(0);
+    'merge: for position in 0..longest {
+        for &id in lists.iter().filter_map(|ids| ids.get(position)) {
+            if clips.len() >= limit {
+                break 'merge;
+            }
+            if !seen.insert(id) {
+                continue;
+            }
+            // Clips deleted since they were indexed are gone here
+            if let Some(clip) = load_clip(conn, id)? {
+                clips.push(clip);
+            }
+        }
+    }
+
+    log!("Total clips found: {}", clips.len());
     Ok(clips)
 }
 
+// Ids of the clips matching a query without fuzzy words, in the order `search_clips` lists them
+fn filtered_clip_ids(conn: &Connection, filters: &SearchQuery, limit: Option<usize>) -> Result<Vec<i64>> {
+    let mut values = Vec::new();
+    let filter = query_sql(filters, &mut values);
+    // -1 is no limit in SQLite
+    values.push(Value::Integer(limit.map_or(-1, |limit| limit as i64)));
+    let mut stmt = conn.prepare(&format!(
+        "SELECT clips.id FROM clips LEFT JOIN clip_origins ON clip_origins.clip_id = clips.id
+         WHERE {filter}
+         ORDER BY clips.pinned DESC, clips.sort_rank ASC, clips.timestamp DESC
+         LIMIT ?{}",
+        values.len()
+    ))?;
+    stmt.query_map(params_from_iter(&values), |row| row.get(0))?.collect()
+}
+
 // pub fn search_tags (conn: &Connection, query: &str) -> Result<Vec(i64, String)> {
 //     
 // }
 
 
-pub fn load_clips_for_tag(conn: &Connection, tag_id: &i64) -> Result<Vec<(i64, String, i64, bool)>> {
-    println!("Loading clips for tag_id: {}", tag_id);
+pub fn load_clips_for_tag(conn: &Connection, tag_id: &i64) -> Result<Vec<ClipRow>> {
+    log!("Loading clips for tag_id: {}", tag_id);
     
-    let mut stmt = conn.prepare(
-        "
-        SELECT clips.id, clips.content, clips.timestamp, clips.pinned
-        FROM clips
+    let mut stmt = conn.prepare(&format!(
+        "{CLIP_SELECT}
         INNER JOIN clip_tags ON clips.id = clip_tags.clip_id
         WHERE clip_tags.tag_id = ?1
-        ORDER BY clips.pinned DESC, clips.timestamp DESC
+        ORDER BY clips.pinned DESC, clips.sort_rank ASC, clips.timestamp DESC
         "
-    )?;
+    ))?;
 
-    let rows = stmt.query_map(params![*tag_id], |row| {
-        Ok((
-            row.get::<_, i64>(0)?,         // clip id
-            row.get::<_, String>(1)?,      // clip content
-            row.get::<_, i64>(2)?,         // timestamp
-            row.get::<_, i64>(3)? != 0     // pinned as bool
-        ))
-    })?;
+    let rows = stmt.query_map(params![*tag_id], clip_row)?;
 
     let mut clips = Vec::new();
     for clip_result in rows {
         match &clip_result {
-            Ok((id, content, timestamp, pinned)) => {
-                println!(
+            Ok((id, content, timestamp, pinned, ..)) => {
+                log!(
                     "Loaded clip (ID: {}): '{}', timestamp: '{}', pinned: {}",
                     id, content, timestamp, pinned
                 );
             }
-            Err(e) => println!("Error loading a clip row: {}", e),
+            Err(e) => log!("Error loading a clip row: {}", e),
         }
         clips.push(clip_result?);
     }
 
-    println!("Total clips loaded for tag {}: {}", tag_id, clips.len());
+    log!("Total clips loaded for tag {}: {}", tag_id, clips.len());
     Ok(clips)
 }
 
 pub fn create_tag(conn: &Connection, name: &str) -> Result<usize> {
-    println!("Creating new tag: {}", name);
+    log!("Creating new tag: {}", name);
     let result = conn.execute(
         "INSERT INTO tags (name) VALUES (?1)",
         rusqlite::params![name],
     );
     match result {
-        Ok(_) => println!("created tag"), 
-        Err(ref e) => println!("Error creating tag: {}", e),
+        Ok(_) => log!("created tag"), 
+        Err(ref e) => log!("Error creating tag: {}", e),
     }
     result
    
 }
 
 pub fn assign_tag_to_clip(conn: &Connection, clip_id: i64, tag_id: i64) -> Result<usize> {
-    println!("Assigning tag {} to clip {}", tag_id, clip_id);
+    log!("Assigning tag {} to clip {}", tag_id, clip_id);
     let result = conn.execute(
         "INSERT INTO clip_tags (clip_id, tag_id) VALUES (?1, ?2)",
         rusqlite::params![clip_id, tag_id],
     );
     match result {
-        Ok(rows) => println!("Created {} row(s).", rows),
-        Err(ref e) => println!("Error assigning tag to clip: {}", e),
+        Ok(rows) => log!("Created {} row(s).", rows),
+        Err(ref e) => log!("Error assigning tag to clip: {}", e),
     }
     result
 }
 
 pub fn delete_clip(conn: &Connection, id: i64) -> Result<usize> {
-    println!("Deleting clip with ID: {}", id);
+    log!("Deleting clip with ID: {}", id);
+    conn.execute("DELETE FROM clip_sources WHERE clip_id = ?1", params![id])?;
+    conn.execute("DELETE FROM clip_images WHERE clip_id = ?1", params![id])?;
+    conn.execute("DELETE FROM clip_formats WHERE clip_id = ?1", params![id])?;
+    conn.execute("DELETE FROM clip_origins WHERE clip_id = ?1", params![id])?;
     let result = conn.execute("DELETE FROM clips WHERE id = ?1", params![id]);
     match result {
-        Ok(rows) => println!("Deleted {} row(s).", rows),
-        Err(ref e) => println!("Error deleting clip: {}", e),
+        Ok(rows) => log!("Deleted {} row(s).", rows),
+        Err(ref e) => log!("Error deleting clip: {}", e),
     }
     result
 }
 
-pub fn save_clip(conn: &Connection, clip: &str, timestamp: i64) -> Result<(), rusqlite::Error> {
-    println!("Saving clip: '{}', timestamp: '{}'", clip, timestamp);
+// Insert a new text clip and return its ID
+pub fn save_clip(conn: &Connection, clip: &str, timestamp: i64) -> Result<i64, rusqlite::Error> {
+    save_clip_of_kind(conn, clip, ClipKind::Text, timestamp)
+}
+
+// Insert a text clip copied from the given selection and return its ID
+pub fn save_selection_clip(conn: &Connection, clip: &str, selection: Selection, timestamp: i64) -> Result<i64> {
+    log!("Saving {} clip: '{}', timestamp: '{}'", selection.as_str(), clip, timestamp);
+    conn.execute(
+        "INSERT INTO clips (content, timestamp, pinned, selection) VALUES (?1, ?2, 0, ?3)",
+        params![clip, timestamp, selection.as_str()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Keep the text as copied for a clip whose content was cleaned up
+pub fn save_clip_original(conn: &Connection, clip_id: i64, original: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE clips SET original_content = ?1 WHERE id = ?2",
+        params![original, clip_id],
+    )?;
+    Ok(())
+}
+
+// Insert a new clip of any kind and return its ID
+pub fn save_clip_of_kind(conn: &Connection, clip: &str, kind: ClipKind, timestamp: i64) -> Result<i64> {
+    log!("Saving {} clip: '{}', timestamp: '{}'", kind.as_str(), clip, timestamp);
     let result = conn.execute(
-        "INSERT INTO clips (content, timestamp, pinned) VALUES (?1, ?2, 0)",
-        params![clip, timestamp],
+        "INSERT INTO clips (content, timestamp, pinned, kind) VALUES (?1, ?2, 0, ?3)",
+        params![clip, timestamp, kind.as_str()],
     );
     match result {
-        Ok(rows) => println!("Inserted {} row(s) into clips.", rows),
-        Err(ref e) => println!("Error inserting clip: {}", e),
+        Ok(rows) => log!("Inserted {} row(s) into clips.", rows),
+        Err(ref e) => log!("Error inserting clip: {}", e),
     }
-    result.map(|_| ())
+    result.map(|_| conn.last_insert_rowid())
 }
 
-pub fn load_recent_clips(conn: &Connection, limit: usize) -> Result<Vec<(i64, String, i64, bool)>> {
-    println!("Loading up to {} recent clips...", limit);
+// Save an image clip, or bump the existing clip with the same pixels to the top
+pub fn save_image_clip(conn: &Connection, image: &ClipImage, timestamp: i64) -> Result<i64> {
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT clip_id FROM clip_images WHERE pixel_hash = ?1",
+            params![image.pixel_hash],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(clip_id) = existing {
+        log!("Image already stored as clip {}, updating timestamp.", clip_id);
+        conn.execute("UPDATE clips SET timestamp = ?1 WHERE id = ?2", params![timestamp, clip_id])?;
+        return Ok(clip_id);
+    }
+
+    log!("Saving image clip: {}x{}, {} bytes", image.width, image.height, image.png.len());
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO clips (content, timestamp, pinned, kind) VALUES (?1, ?2, 0, ?3)",
+        params![image.label(), timestamp, ClipKind::Image.as_str()],
+    )?;
+    let clip_id = tx.last_insert_rowid();
+    tx.execute(
+        "INSERT INTO clip_images (clip_id, png, thumbnail, width, height, pixel_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![clip_id, image.png, image.thumbnail, image.width, image.height, image.pixel_hash],
+    )?;
+    tx.commit()?;
+    Ok(clip_id)
+}
+
+// Record where a clip was copied from, replacing what was stored before
+pub fn save_clip_origin(conn: &Connection, clip_id: i64, origin: &ClipOrigin) -> Result<()> {
+    if origin.is_empty() {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO clip_origins (clip_id, app, process, window_title, owner, hostname)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![clip_id, origin.app, origin.process, origin.window_title, origin.owner, origin.hostname],
+    )?;
+    Ok(())
+}
+
+// Full-size PNG bytes of an image clip
+pub fn load_clip_image(conn: &Connection, clip_id: i64) -> Result<Vec<u8>> {
+    conn.query_row(
+        "SELECT png FROM clip_images WHERE clip_id = ?1",
+        params![clip_id],
+        |row| row.get(0),
+    )
+}
+
+// Store the rich representations captured alongside a clip's plain text
+pub fn save_clip_formats(conn: &Connection, clip_id: i64, formats: &[ClipFormat]) -> Result<()> {
+    if formats.is_empty() {
+        return Ok(());
+    }
+    log!("Saving {} format(s) for clip {}", formats.len(), clip_id);
+    let tx = conn.unchecked_transaction()?;
+    for format in formats {
+        tx.execute(
+            "INSERT OR REPLACE INTO clip_formats (clip_id, mime, data) VALUES (?1, ?2, ?3)",
+            params![clip_id, format.mime, format.data],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn load_clip_formats(conn: &Connection, clip_id: i64) -> Result<Vec<ClipFormat>> {
     let mut stmt = conn.prepare(
-        "SELECT id, content, timestamp, pinned FROM clips 
-         ORDER BY pinned DESC, timestamp DESC 
-         LIMIT ?",
+        "SELECT mime, data FROM clip_formats WHERE clip_id = ?1 ORDER BY mime ASC"
     )?;
-    let rows = stmt.query_map([limit as i64], |row| {
-        Ok((
-            row.get::<_, i64>(0)?,      // id
-            row.get::<_, String>(1)?,   // content
-            row.get::<_, i64>(2)?,      // timestamp
-            row.get::<_, i64>(3)? != 0, // pinned (as bool)
-        ))
+    let formats = stmt.query_map(params![clip_id], |row| {
+        Ok(ClipFormat {
+            mime: row.get(0)?,
+            data: row.get(1)?,
+        })
     })?;
+    formats.collect()
+}
+
+// Remember which clips (in order) a combined clip was built from
+pub fn save_clip_sources(conn: &Connection, clip_id: i64, source_ids: &[i64]) -> Result<()> {
+    log!("Recording {} source(s) for clip {}", source_ids.len(), clip_id);
+    let tx = conn.unchecked_transaction()?;
+    for (position, source_id) in source_ids.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO clip_sources (clip_id, source_clip_id, position) VALUES (?1, ?2, ?3)",
+            params![clip_id, source_id, position as i64],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn load_recent_clips(conn: &Connection, limit: usize) -> Result<Vec<ClipRow>> {
+    log!("Loading up to {} recent clips...", limit);
+    let mut stmt = conn.prepare(&format!(
+        "{CLIP_SELECT}
+         ORDER BY pinned DESC, sort_rank ASC, timestamp DESC 
+         LIMIT ?",
+    ))?;
+    let rows = stmt.query_map([limit as i64], clip_row)?;
     let mut clips = Vec::new();
     for clip in rows {
         match &clip {
-            Ok((id, content, timestamp, pinned)) => {
-                println!(
+            Ok((id, content, timestamp, pinned, ..)) => {
+                log!(
                     "Loaded clip (ID: {}): '{}', timestamp: '{}', pinned: {}",
                     id, content, timestamp, pinned
                 );
             }
-            Err(e) => println!("Error loading a clip row: {}", e),
+            Err(e) => log!("Error loading a clip row: {}", e),
         }
         clips.push(clip?);
     }
-    println!("Total clips loaded: {}", clips.len());
+    log!("Total clips loaded: {}", clips.len());
+    Ok(clips)
+}
+
+// The newest clip copied to the given selection
+pub fn load_last_clip(conn: &Connection, selection: Selection) -> Result<Option<ClipRow>> {
+    conn.query_row(
+        &format!(
+            "{CLIP_SELECT}
+             WHERE selection = ?1
+             ORDER BY timestamp DESC, clips.id DESC
+             LIMIT 1",
+        ),
+        params![selection.as_str()],
+        clip_row,
+    )
+    .optional()
+}
+
+pub fn load_clips_for_selection(conn: &Connection, selection: Selection, limit: usize) -> Result<Vec<ClipRow>> {
+    log!("Loading up to {} {} clips...", limit, selection.as_str());
+    let mut stmt = conn.prepare(&format!(
+        "{CLIP_SELECT}
+         WHERE selection = ?1
+         ORDER BY pinned DESC, sort_rank ASC, timestamp DESC
+         LIMIT ?2",
+    ))?;
+    let rows = stmt.query_map(params![selection.as_str(), limit as i64], clip_row)?;
+    let clips = rows.collect::<Result<Vec<_>>>()?;
+    log!("Total {} clips loaded: {}", selection.as_str(), clips.len());
     Ok(clips)
 }
 
+// Save each piece of a split clip as its own clip. The new clips inherit the source
+// clip's tags, plus `extra_tag` if given, and remember the clip they were split from.
+pub fn save_split_clips(conn: &Connection, source_id: i64, pieces: &[String], extra_tag: Option<i64>) -> Result<Vec<i64>> {
+    log!("Splitting clip {} into {} clip(s)", source_id, pieces.len());
+    let timestamp = chrono::Utc::now().timestamp();
+    let tx = conn.unchecked_transaction()?;
+    let mut new_ids = Vec::with_capacity(pieces.len());
+    for piece in pieces {
+        let clip_id = save_clip(&tx, piece, timestamp)?;
+        tx.execute(
+            "INSERT INTO clip_tags (clip_id, tag_id)
+             SELECT ?1, tag_id FROM clip_tags WHERE clip_id = ?2",
+            params![clip_id, source_id],
+        )?;
+        if let Some(tag_id) = extra_tag {
+            tx.execute(
+                "INSERT OR IGNORE INTO clip_tags (clip_id, tag_id) VALUES (?1, ?2)",
+                params![clip_id, tag_id],
+            )?;
+        }
+        tx.execute(
+            "INSERT INTO clip_sources (clip_id, source_clip_id, position) VALUES (?1, ?2, 0)",
+            params![clip_id, source_id],
+        )?;
+        new_ids.push(clip_id);
+    }
+    tx.commit()?;
+    Ok(new_ids)
+}
+
+// Set or clear the title and note of a clip; blank values are stored as NULL
+pub fn update_clip_details(conn: &Connection, id: i64, title: &str, note: &str) -> Result<usize> {
+    log!("Updating details for clip {}: title='{}'", id, title);
+    let title = Some(title.trim()).filter(|t| !t.is_empty());
+    let note = Some(note.trim()).filter(|n| !n.is_empty());
+    conn.execute(
+        "UPDATE clips SET title = ?1, note = ?2 WHERE id = ?3",
+        params![title, note, id],
+    )
+}
+
+// Newly pinned clips go to the bottom of the pinned list, unpinned clips lose their rank
 pub fn toggle_pin_clip(conn: &Connection, id: i64) -> Result<usize> {
-    println!("Toggling pin for clip with ID: {}", id);
+    log!("Toggling pin for clip with ID: {}", id);
     conn.execute(
-        "UPDATE clips SET pinned = NOT pinned WHERE id = ?1",
+        "UPDATE clips SET
+            pinned = NOT pinned,
+            sort_rank = CASE WHEN pinned THEN 0
+                ELSE (SELECT COALESCE(MAX(sort_rank), 0) + 1 FROM clips WHERE pinned = 1) END
+         WHERE id = ?1",
         params![id],
     )
 }
 
+// IDs of all pinned clips in their display order
+pub fn load_pinned_clip_ids(conn: &Connection) -> Result<Vec<i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM clips WHERE pinned = 1 ORDER BY sort_rank ASC, timestamp DESC"
+    )?;
+    let ids = stmt.query_map([], |row| row.get::<_, i64>(0))?;
+    ids.collect()
+}
+
+// Move a pinned clip to `new_index` in the pinned list and renumber every pinned clip.
+// Indexes past the end move the clip to the bottom; unpinned clips are left alone.
+pub fn move_pinned_clip(conn: &Connection, id: i64, new_index: usize) -> Result<()> {
+    log!("Moving pinned clip {} to position {}", id, new_index);
+    let mut ids = load_pinned_clip_ids(conn)?;
+    let Some(old_index) = ids.iter().position(|&pinned_id| pinned_id == id) else {
+        log!("Clip {} is not pinned, nothing to move.", id);
+        return Ok(());
+    };
+    ids.remove(old_index);
+    ids.insert(new_index.min(ids.len()), id);
+
+    let tx = conn.unchecked_transaction()?;
+    for (rank, pinned_id) in ids.iter().enumerate() {
+        tx.execute(
+            "UPDATE clips SET sort_rank = ?1 WHERE id = ?2",
+            params![rank as i64, pinned_id],
+        )?;
+    }
+    tx.commit()
+}
+
 // Load clips that fall on a specific UTC date
 // Load clips that fall on a specific LOCAL date (converts to UTC for query)
 pub fn load_clips_for_date(
     conn: &Connection,
     date: chrono::NaiveDate,
-) -> Result<Vec<(i64, String, i64, bool)>> {
+) -> Result<Vec<ClipRow>> {
     use chrono::{Local, TimeZone};
 
     // Create start and end of day in LOCAL timezone
@@ -383,40 +1004,362 @@ pub fn load_clips_for_date(
         .unwrap()
         .timestamp();
 
-    println!(
+    log!(
         "Loading clips between {} (inclusive) and {} (exclusive)",
         start_ts, end_ts
     );
 
-    let mut stmt = conn.prepare(
-        "SELECT id, content, timestamp, pinned FROM clips
+    let mut stmt = conn.prepare(&format!(
+        "{CLIP_SELECT}
          WHERE timestamp >= ?1 AND timestamp < ?2
-         ORDER BY pinned DESC, timestamp DESC",
-    )?;
+         ORDER BY pinned DESC, sort_rank ASC, timestamp DESC",
+    ))?;
 
-    let rows = stmt.query_map(params![start_ts, end_ts], |row| {
-        Ok((
-            row.get::<_, i64>(0)?,      // id
-            row.get::<_, String>(1)?,   // content
-            row.get::<_, i64>(2)?,      // timestamp
-            row.get::<_, i64>(3)? != 0, // pinned (as bool)
-        ))
-    })?;
+    let rows = stmt.query_map(params![start_ts, end_ts], clip_row)?;
 
     let mut clips = Vec::new();
     for clip in rows {
         match &clip {
-            Ok((id, content, timestamp, pinned)) => {
-                println!(
+            Ok((id, content, timestamp, pinned, ..)) => {
+                log!(
                     "Loaded clip (ID: {}): '{}', timestamp: '{}', pinned: {}",
                     id, content, timestamp, pinned
                 );
             }
-            Err(e) => println!("Error loading a clip row: {}", e),
+            Err(e) => log!("Error loading a clip row: {}", e),
         }
         clips.push(clip?);
     }
 
-    println!("Total clips loaded for date: {}", clips.len());
+    log!("Total clips loaded for date: {}", clips.len());
     Ok(clips)
-}
\ No newline at end of file
+}
+pub fn load_app_state(conn: &Connection, key: &str) -> Result<Option<String>> {
+    conn.query_row("SELECT value FROM app_state WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()
+}
+
+// Store a value, None removes the key
+pub fn save_app_state(conn: &Connection, key: &str, value: Option<&str>) -> Result<()> {
+    match value {
+        Some(value) => conn.execute(
+            "INSERT INTO app_state (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?,
+        None => conn.execute("DELETE FROM app_state WHERE key = ?1", params![key])?,
+    };
+    Ok(())
+}
+
+pub fn load_pause_state(conn: &Connection) -> Result<PauseState> {
+    let value = load_app_state(conn, "pause")?;
+    Ok(PauseState::from_db(value.as_deref(), chrono::Utc::now().timestamp()))
+}
+
+pub fn save_pause_state(conn: &Connection, state: PauseState) -> Result<()> {
+    log!("Clipboard recording: {}", state.label(chrono::Utc::now().timestamp()));
+    save_app_state(conn, "pause", state.to_db().as_deref())
+}
+
+pub fn save_capture_log(conn: &Connection, entry: &CaptureLogEntry) -> Result<()> {
+    conn.execute(
+        "INSERT INTO capture_log (timestamp, accepted, stage, detail, preview, clip_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![entry.timestamp, entry.accepted, entry.stage, entry.detail, entry.preview, entry.clip_id],
+    )?;
+    conn.execute(
+        "DELETE FROM capture_log WHERE id <= (SELECT MAX(id) FROM capture_log) - ?1",
+        params![CAPTURE_LOG_LIMIT],
+    )?;
+    Ok(())
+}
+
+// Newest first; only the dropped clips unless `include_accepted`
+pub fn load_capture_log(conn: &Connection, include_accepted: bool, limit: usize) -> Result<Vec<CaptureLogEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, accepted, stage, detail, preview, clip_id FROM capture_log
+         WHERE accepted = 0 OR ?1
+         ORDER BY id DESC LIMIT ?2",
+    )?;
+    let entries = stmt
+        .query_map(params![include_accepted, limit as i64], |row| {
+            Ok(CaptureLogEntry {
+                timestamp: row.get(0)?,
+                accepted: row.get(1)?,
+                stage: row.get(2)?,
+                detail: row.get(3)?,
+                preview: row.get(4)?,
+                clip_id: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(entries)
+}
+
+pub fn clear_capture_log(conn: &Connection) -> Result<()> {
+    log!("Clearing capture log");
+    conn.execute("DELETE FROM capture_log", [])?;
+    Ok(())
+}
+
+pub fn load_clip(conn: &Connection, id: i64) -> Result<Option<ClipRow>> {
+    conn.query_row(&format!("{CLIP_SELECT} WHERE clips.id = ?1"), params![id], clip_row)
+        .optional()
+}
+
+pub fn remove_tag_from_clip(conn: &Connection, clip_id: i64, tag_id: i64) -> Result<usize> {
+    log!("Removing tag {} from clip {}", tag_id, clip_id);
+    conn.execute(
+        "DELETE FROM clip_tags WHERE clip_id = ?1 AND tag_id = ?2",
+        params![clip_id, tag_id],
+    )
+}
+
+pub fn find_tag(conn: &Connection, name: &str) -> Result<Option<i64>> {
+    conn.query_row("SELECT id FROM tags WHERE name = ?1", params![name], |row| row.get(0))
+        .optional()
+}
+
+// Add tags to a clip by name, creating missing tags. Tags it already has are left alone.
+pub fn tag_clip(conn: &Connection, clip_id: i64, names: &[String]) -> Result<()> {
+    for name in names {
+        let (tag_id, _) = find_or_create_tag(conn, name)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO clip_tags (clip_id, tag_id) VALUES (?1, ?2)",
+            params![clip_id, tag_id],
+        )?;
+    }
+    Ok(())
+}
+
+// Add a text clip by hand (command line, IPC) with its details in one go and return its ID
+pub fn add_text_clip(
+    conn: &Connection,
+    text: &str,
+    title: Option<&str>,
+    note: Option<&str>,
+    tags: &[String],
+    pin: bool,
+) -> Result<i64> {
+    let id = save_clip(conn, text, chrono::Utc::now().timestamp())?;
+    if title.is_some() || note.is_some() {
+        update_clip_details(conn, id, title.unwrap_or(""), note.unwrap_or(""))?;
+    }
+    tag_clip(conn, id, tags)?;
+    if pin {
+        set_pinned(conn, id, true)?;
+    }
+    Ok(id)
+}
+
+// ID of the tag with this name, and whether it had to be created
+pub fn find_or_create_tag(conn: &Connection, name: &str) -> Result<(i64, bool)> {
+    if let Some(id) = find_tag(conn, name)? {
+        return Ok((id, false));
+    }
+    create_tag(conn, name)?;
+    Ok((conn.last_insert_rowid(), true))
+}
+
+// Pin or unpin a clip; clips already in that state keep their place
+pub fn set_pinned(conn: &Connection, id: i64, pinned: bool) -> Result<usize> {
+    let current: Option<bool> = conn
+        .query_row("SELECT pinned FROM clips WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()?;
+    match current {
+        Some(current) if current != pinned => toggle_pin_clip(conn, id),
+        _ => Ok(0),
+    }
+}
+
+pub fn load_stats(conn: &Connection) -> Result<VaultStats> {
+    let mut stats = conn.query_row(
+        "SELECT COUNT(*),
+                COALESCE(SUM(kind = 'text'), 0), COALESCE(SUM(kind = 'image'), 0), COALESCE(SUM(kind = 'files'), 0),
+                COALESCE(SUM(pinned), 0), COALESCE(SUM(selection = 'primary'), 0),
+                MIN(timestamp), MAX(timestamp)
+         FROM clips",
+        [],
+        |row| {
+            Ok(VaultStats {
+                clips: row.get(0)?,
+                text: row.get(1)?,
+                images: row.get(2)?,
+                files: row.get(3)?,
+                pinned: row.get(4)?,
+                primary: row.get(5)?,
+                oldest: row.get(6)?,
+                newest: row.get(7)?,
+                ..VaultStats::default()
+            })
+        },
+    )?;
+    stats.tags = conn.query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0))?;
+    stats.database_bytes = std::fs::metadata(get_db_path()).map(|meta| meta.len()).unwrap_or(0);
+    Ok(stats)
+}
+
+// Everything needed to rebuild the vault elsewhere, including the order of pinned clips
+pub fn export_vault(conn: &Connection) -> Result<VaultExport> {
+    log!("Exporting vault...");
+    let tags = load_tags(conn)?
+        .into_iter()
+        .map(|(_, name, color)| ExportTag { name, color })
+        .collect();
+    let clip_tags = load_clip_tags(conn)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, content, timestamp, pinned, sort_rank, title, note, kind, selection, original_content
+         FROM clips ORDER BY id",
+    )?;
+    let mut clips = stmt
+        .query_map([], |row| {
+            Ok(ExportClip {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                timestamp: row.get(2)?,
+                pinned: row.get(3)?,
+                sort_rank: row.get(4)?,
+                title: row.get(5)?,
+                note: row.get(6)?,
+                kind: row.get(7)?,
+                selection: row.get(8)?,
+                original: row.get(9)?,
+                tags: Vec::new(),
+                formats: Vec::new(),
+                image: None,
+                origin: None,
+                sources: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut sources = conn.prepare("SELECT source_clip_id FROM clip_sources WHERE clip_id = ?1 ORDER BY position")?;
+    for clip in &mut clips {
+        clip.tags = clip_tags.get(&clip.id).cloned().unwrap_or_default();
+        clip.formats = load_clip_formats(conn, clip.id)?
+            .into_iter()
+            .map(|format| ExportFormat { mime: format.mime, data: BASE64.encode(format.data) })
+            .collect();
+        clip.image = load_clip_image(conn, clip.id).optional()?.map(|png| BASE64.encode(png));
+        clip.origin = conn
+            .query_row(
+                "SELECT app, process, window_title, owner, hostname FROM clip_origins WHERE clip_id = ?1",
+                params![clip.id],
+                |row| clip_origin(row, 0),
+            )
+            .optional()?
+            .flatten();
+        clip.sources = sources
+            .query_map(params![clip.id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>>>()?;
+    }
+    log!("Exported {} clip(s)", clips.len());
+
+    Ok(VaultExport {
+        version: EXPORT_VERSION,
+        exported_at: chrono::Utc::now().timestamp(),
+        tags,
+        clips,
+    })
+}
+
+// Add the clips of an export to this vault. Clips already here (same kind, content and
+// timestamp) are skipped; imported pinned clips go after the ones already pinned.
+pub fn import_vault(conn: &Connection, export: &VaultExport) -> Result<ImportSummary, Box<dyn Error>> {
+    if export.version > EXPORT_VERSION {
+        return Err(format!("export version {} is newer than this ClipVault supports", export.version).into());
+    }
+    log!("Importing {} clip(s)...", export.clips.len());
+    let tx = conn.unchecked_transaction()?;
+    let mut summary = ImportSummary::default();
+
+    for tag in &export.tags {
+        let (tag_id, created) = find_or_create_tag(&tx, &tag.name)?;
+        if created {
+            summary.tags_created += 1;
+            update_tag_color(&tx, tag_id, tag.color.as_deref())?;
+        }
+    }
+
+    // Imported pins go below the existing ones, in their exported order
+    let rank_offset: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(sort_rank) + 1, 0) FROM clips WHERE pinned = 1",
+        [],
+        |row| row.get(0),
+    )?;
+    let mut new_ids: HashMap<i64, i64> = HashMap::new();
+    let mut imported = Vec::new();
+    for clip in &export.clips {
+        let existing: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM clips WHERE kind = ?1 AND content = ?2 AND timestamp = ?3",
+                params![clip.kind, clip.content, clip.timestamp],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = existing {
+            new_ids.insert(clip.id, id);
+            summary.skipped += 1;
+            continue;
+        }
+
+        let sort_rank = if clip.pinned { clip.sort_rank + rank_offset } else { 0 };
+        tx.execute(
+            "INSERT INTO clips (content, timestamp, pinned, sort_rank, title, note, kind, selection, original_content)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                clip.content, clip.timestamp, clip.pinned, sort_rank, clip.title, clip.note,
+                clip.kind, clip.selection, clip.original
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        new_ids.insert(clip.id, id);
+        imported.push((id, clip));
+
+        for name in &clip.tags {
+            let (tag_id, created) = find_or_create_tag(&tx, name)?;
+            summary.tags_created += created as usize;
+            tx.execute(
+                "INSERT OR IGNORE INTO clip_tags (clip_id, tag_id) VALUES (?1, ?2)",
+                params![id, tag_id],
+            )?;
+        }
+        for format in &clip.formats {
+            tx.execute(
+                "INSERT OR REPLACE INTO clip_formats (clip_id, mime, data) VALUES (?1, ?2, ?3)",
+                params![id, format.mime, BASE64.decode(&format.data)?],
+            )?;
+        }
+        if let Some(png) = &clip.image {
+            let pixels = decode_png(&BASE64.decode(png)?)?;
+            let image = ClipImage::from_rgba(pixels.width(), pixels.height(), pixels.into_raw())?;
+            tx.execute(
+                "INSERT INTO clip_images (clip_id, png, thumbnail, width, height, pixel_hash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![id, image.png, image.thumbnail, image.width, image.height, image.pixel_hash],
+            )?;
+        }
+        if let Some(origin) = &clip.origin {
+            save_clip_origin(&tx, id, origin)?;
+        }
+        summary.imported += 1;
+    }
+
+    // Sources may come later in the file than the clips made from them
+    for (id, clip) in imported {
+        for (position, source) in clip.sources.iter().enumerate() {
+            if let Some(source_id) = new_ids.get(source) {
+                tx.execute(
+                    "INSERT INTO clip_sources (clip_id, source_clip_id, position) VALUES (?1, ?2, ?3)",
+                    params![id, source_id, position as i64],
+                )?;
+            }
+        }
+    }
+
+    tx.commit()?;
+    log!("Imported {} clip(s), skipped {}", summary.imported, summary.skipped);
+    Ok(summary)
+}
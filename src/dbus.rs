@@ -0,0 +1,155 @@
+// Session bus interface for desktop integrations (GNOME extensions, KDE widgets, busctl):
+// the daemon owns org.clipvault.Vault and exports the vault at /org/clipvault/Vault.
+//
+// Clips are (id, kind, content, timestamp, pinned) structs, signature (xssxb).
+use std::sync::{ Arc, Mutex };
+use rusqlite::Connection;
+use zbus::{ blocking::{ self, connection::Builder }, interface, object_server::SignalEmitter };
+
+use crate::cli::copy_source;
+use crate::clipboard;
+use crate::db;
+use crate::models::{ Clip, DaemonEvent, DaemonNotifier, PauseState };
+
+pub const BUS_NAME: &str = "org.clipvault.Vault";
+pub const OBJECT_PATH: &str = "/org/clipvault/Vault";
+
+type ClipStruct = (i64, String, String, i64, bool);
+
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.clipvault.Vault.Error")]
+pub enum VaultError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    NotFound(String),
+    InvalidArgs(String),
+    Clipboard(String),
+    Failed(String),
+}
+
+impl From<rusqlite::Error> for VaultError {
+    fn from(e: rusqlite::Error) -> Self {
+        VaultError::Failed(e.to_string())
+    }
+}
+
+struct Vault {
+    db: Arc<Mutex<Connection>>,
+    notify: DaemonNotifier,
+}
+
+impl Vault {
+    fn with_db<T>(&self, f: impl FnOnce(&Connection) -> Result<T, VaultError>) -> Result<T, VaultError> {
+        let conn = self.db.lock().map_err(|_| VaultError::Failed("database lock poisoned".to_string()))?;
+        f(&conn)
+    }
+
+    fn set_pause_state(&self, state: PauseState) -> Result<(), VaultError> {
+        self.with_db(|conn| Ok(db::save_pause_state(conn, state)?))?;
+        (self.notify)(DaemonEvent::PauseChanged(state));
+        Ok(())
+    }
+}
+
+#[interface(name = "org.clipvault.Vault")]
+impl Vault {
+    // Newest clips, pinned ones first
+    fn history(&self, limit: u32) -> Result<Vec<ClipStruct>, VaultError> {
+        self.with_db(|conn| Ok(clip_structs(db::load_recent_clips(conn, limit as usize)?)))
+    }
+
+    // Same query syntax as the search box
+    fn search(&self, query: &str, limit: u32) -> Result<Vec<ClipStruct>, VaultError> {
+        self.with_db(|conn| Ok(clip_structs(db::search_clips(conn, query, Some(limit as usize))?)))
+    }
+
+    fn copy(&self, id: i64) -> Result<(), VaultError> {
+        let captured = self.with_db(|conn| {
+            let clip = db::load_clip(conn, id)?
+                .map(Clip::from_tuple)
+                .ok_or_else(|| VaultError::NotFound(format!("clip {} not found", id)))?;
+            copy_source(conn, clip, false).map_err(|missing| VaultError::NotFound(format!("{} not found", missing)))
+        })?;
+        clipboard::copy_captured(&captured).map_err(|e| VaultError::Clipboard(e.to_string()))
+    }
+
+    // Stop recording for `minutes`, or until resumed when 0
+    fn pause(&self, minutes: u32) -> Result<(), VaultError> {
+        let state = match minutes {
+            0 => PauseState::Paused,
+            minutes => PauseState::checked_incognito(minutes as i64, chrono::Utc::now().timestamp()).ok_or_else(|| {
+                VaultError::InvalidArgs(format!("minutes must be at most {}", PauseState::MAX_INCOGNITO_MINUTES))
+            })?,
+        };
+        self.set_pause_state(state)
+    }
+
+    fn resume(&self) -> Result<(), VaultError> {
+        self.set_pause_state(PauseState::Recording)
+    }
+
+    // Whether clips are being recorded, and a label like "Incognito, 12 min left"
+    fn status(&self) -> Result<(bool, String), VaultError> {
+        let state = self.with_db(|conn| Ok(db::load_pause_state(conn)?))?;
+        Ok((state.is_recording(), state.label(chrono::Utc::now().timestamp())))
+    }
+
+    #[zbus(signal)]
+    async fn clip_added(
+        emitter: &SignalEmitter<'_>,
+        id: i64,
+        kind: &str,
+        content: &str,
+        timestamp: i64,
+    ) -> zbus::Result<()>;
+}
+
+fn clip_structs(rows: Vec<db::ClipRow>) -> Vec<ClipStruct> {
+    rows.into_iter().map(Clip::from_tuple).map(clip_struct).collect()
+}
+
+fn clip_struct(clip: Clip) -> ClipStruct {
+    (clip.id, clip.kind.as_str().to_string(), clip.content, clip.timestamp, clip.pinned)
+}
+
+// Keeps the bus connection, and with it the exported object, alive
+#[derive(Clone)]
+pub struct DbusService {
+    connection: blocking::Connection,
+}
+
+impl DbusService {
+    // Emit ClipAdded for a clip the monitor just saved
+    pub fn clip_added(&self, conn: &Connection, clip_id: i64) {
+        let clip = match db::load_clip(conn, clip_id) {
+            Ok(Some(row)) => Clip::from_tuple(row),
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("Failed to load clip {} for D-Bus: {}", clip_id, e);
+                return;
+            }
+        };
+        let result = self.connection
+            .object_server()
+            .interface::<_, Vault>(OBJECT_PATH)
+            .and_then(|iface| {
+                zbus::block_on(
+                    Vault::clip_added(iface.signal_emitter(), clip.id, clip.kind.as_str(), &clip.content, clip.timestamp),
+                )
+            });
+        if let Err(e) = result {
+            eprintln!("Failed to emit ClipAdded: {}", e);
+        }
+    }
+}
+
+// Export the vault on the session bus
+pub fn serve(db: Arc<Mutex<Connection>>, notify: DaemonNotifier) -> zbus::Result<DbusService> {
+    serve_on(Builder::session()?, db, notify)
+}
+
+// Export the vault on the bus `builder` connects to, e.g. a private bus in tests
+pub fn serve_on(builder: Builder<'_>, db: Arc<Mutex<Connection>>, notify: DaemonNotifier) -> zbus::Result<DbusService> {
+    let connection = builder.name(BUS_NAME)?.serve_at(OBJECT_PATH, Vault { db, notify })?.build()?;
+    Ok(DbusService { connection })
+}
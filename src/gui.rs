@@ -1,10 +1,15 @@
+use crate::cli;
 use crate::db::{ self };
-use crate::models::{ Clip, Tag, UiMode, UiState };
+use crate::models::{ Clip, PauseState, Tag, UiMode, UiState };
 use crate::settings::{ Settings, Theme };
 use crate::ui::components::main_top_panel::{ TopPanel };
 use crate::ui::views::main_view::MainView;
 use crate::ui::views::tag_filter_view::TagFilterView;
 use crate::ui::views::settings_view::SettingsView;
+use crate::ui::views::capture_log_view::CaptureLogView;
+use crate::utils::fuzzy::{ FuzzyIndex, FuzzyMatcher, byte_ranges, clip_text };
+use crate::utils::search::{ SearchMode, SearchQuery };
+use crate::utils::urls::UrlRule;
 use eframe::egui;
 use rusqlite::Connection;
 use std::collections::HashMap;
@@ -19,6 +24,7 @@ pub struct ClipVaultApp {
     ui_state: UiState,
     tags: Vec<Tag>,
     clip_tags: HashMap<i64, Vec<String>>,
+    fuzzy_index: FuzzyIndex,
 }
 
 impl ClipVaultApp {
@@ -35,6 +41,7 @@ impl ClipVaultApp {
             settings_path,
             tags: Vec::new(),
             clip_tags: HashMap::new(),
+            fuzzy_index: FuzzyIndex::default(),
             window_visible: true,
             ui_state: UiState::default(),
         };
@@ -43,21 +50,97 @@ impl ClipVaultApp {
         app.load_clips_based_on_state();
         app.reload_tags();
         app.clip_tags = db::load_clip_tags(&app.db).unwrap_or_default();
+        app.ui_state.pause_state = db::load_pause_state(&app.db).unwrap_or_default();
+        app.reload_capture_log();
 
         app
     }
 
     // Helper methods to reduce duplication
     fn load_clips_based_on_state(&mut self) {
-        self.clips = if self.ui_state.search_query.is_empty() {
-            db::load_recent_clips(&self.db, self.ui_state.clip_limit)
+        let query = &self.ui_state.search_query;
+        let flags = self.ui_state.regex_flags;
+        // Qualifiers with unusable values are searched as text, the first one is pointed out
+        self.ui_state.search_error = match self.ui_state.search_mode {
+            SearchMode::Regex => None,
+            _ => SearchQuery::parse(query).errors.into_iter().next(),
+        };
+        self.clips = if !query.is_empty() && self.ui_state.search_mode == SearchMode::Regex {
+            match flags.compile(query) {
+                Ok(_) => db::regex_search_clips(&self.db, &flags.apply(query), Some(self.ui_state.clip_limit)),
+                Err(e) => {
+                    self.ui_state.search_error = Some(e.to_string());
+                    Ok(Vec::new())
+                }
+            }
+        } else if !query.is_empty() && self.ui_state.search_mode == SearchMode::Fuzzy {
+            db::fuzzy_search_clips(&self.db, &mut self.fuzzy_index, query, Some(self.ui_state.clip_limit))
+        } else if !query.is_empty() {
+            db::search_clips(&self.db, query, Some(self.ui_state.clip_limit))
+        } else if let Some(selection) = self.ui_state.selection_filter {
+            db::load_clips_for_selection(&self.db, selection, self.ui_state.clip_limit)
         } else {
-            db::search_clips(&self.db, &self.ui_state.search_query, Some(self.ui_state.clip_limit))
+            db::load_recent_clips(&self.db, self.ui_state.clip_limit)
         }
             .unwrap_or_default()
             .into_iter()
             .map(Clip::from_tuple)
             .collect();
+        // Files may have been moved or deleted since the last load
+        self.ui_state.copied_files.clear();
+        self.update_search_highlights();
+    }
+
+    // Mark what the query matched in each listed clip, done once per search, not per frame
+    fn update_search_highlights(&mut self) {
+        self.ui_state.search_highlights.clear();
+        let query = &self.ui_state.search_query;
+        let texts = self.clips.iter().filter(|clip| clip.is_text());
+        match self.ui_state.search_mode {
+            SearchMode::Substring => {}
+            SearchMode::Fuzzy => {
+                // The first alternative whose words match a clip marks it. Clips are matched like
+                // they were ranked, with title and note, but only the content is highlighted.
+                let mut matchers: Vec<FuzzyMatcher> = SearchQuery::parse(query)
+                    .split_text()
+                    .into_iter()
+                    .map(|(words, _)| FuzzyMatcher::new(&words))
+                    .filter(|matcher| !matcher.is_empty())
+                    .collect();
+                for clip in texts {
+                    let (text, content) = clip_text(clip.title.as_deref(), &clip.content, clip.note.as_deref());
+                    if let Some(found) = matchers.iter_mut().find_map(|matcher| matcher.find(&text)) {
+                        let positions: Vec<usize> = found.positions
+                            .iter()
+                            .filter(|position| content.contains(position))
+                            .map(|position| position - content.start)
+                            .collect();
+                        self.ui_state.search_highlights.insert(clip.id, byte_ranges(&clip.content, &positions));
+                    }
+                }
+            }
+            SearchMode::Regex => {
+                if query.is_empty() {
+                    return;
+                }
+                let Ok(regex) = self.ui_state.regex_flags.compile(query) else {
+                    return;
+                };
+                for clip in texts {
+                    let ranges: Vec<_> = regex
+                        .find_iter(&clip.content)
+                        .map(|found| found.range())
+                        .filter(|range| !range.is_empty())
+                        .collect();
+                    self.ui_state.search_highlights.insert(clip.id, ranges);
+                }
+            }
+        }
+    }
+
+    fn reload_capture_log(&mut self) {
+        self.ui_state.capture_log = db::load_capture_log(&self.db, self.ui_state.capture_log_show_accepted, 200)
+            .unwrap_or_default();
     }
 
     fn reload_tags(&mut self) {
@@ -71,7 +154,10 @@ impl ClipVaultApp {
     fn reset_to_main_state(&mut self) {
         self.ui_state.ui_mode = UiMode::Main;
         self.ui_state.search_query = "".to_string();
+        self.ui_state.selection_filter = None;
         self.ui_state.date_filter = chrono::Utc::now().date_naive();
+        self.ui_state.pause_state = db::load_pause_state(&self.db).unwrap_or_default();
+        self.fuzzy_index.clear(); // picks up edited titles and notes
         self.load_clips_based_on_state();
     }
 
@@ -81,14 +167,45 @@ impl ClipVaultApp {
             .map(|t| (t.id, t.name.clone(), t.color.clone()))
             .collect()
     }
-}
 
-impl eframe::App for ClipVaultApp {
-    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+    // Write the settings file and have a running daemon pick up the monitor and filter settings
+    fn save_settings(&mut self) {
         self.settings.theme = if self.darkmode { Theme::Dark } else { Theme::Light };
         self.settings.mode = self.ui_state.ui_mode;
         self.settings.auto_hide_clips = self.ui_state.auto_hide_clips;
-        let _ = self.settings.save(&self.settings_path);
+        self.settings.monitor_primary = self.ui_state.monitor_primary;
+        self.settings.clipboard_backend = self.ui_state.clipboard_backend;
+        self.settings.excluded_apps = self.ui_state.excluded_apps_text
+            .lines()
+            .map(str::trim)
+            .filter(|app| !app.is_empty())
+            .map(String::from)
+            .collect();
+        self.settings.coalesce_window_ms = self.ui_state.coalesce_window_ms;
+        self.settings.skip_cleared_copies = self.ui_state.skip_cleared_copies;
+        self.settings.selection_sync = self.ui_state.selection_sync;
+        self.settings.keep_clipboard_alive = self.ui_state.keep_clipboard_alive;
+        self.settings.restore_clipboard_on_startup = self.ui_state.restore_clipboard_on_startup;
+        self.settings.url_cleaning = self.ui_state.url_cleaning.clone();
+        self.settings.url_cleaning.rules = self.ui_state.url_rules_text.lines().filter_map(UrlRule::parse_line).collect();
+        self.settings.search_mode = self.ui_state.search_mode;
+        self.settings.regex_flags = self.ui_state.regex_flags;
+        self.settings.capture_filter = self.ui_state.capture_filter.clone();
+        self.settings.capture_filter.ignore_patterns = self.ui_state.ignore_patterns_text
+            .lines()
+            .filter(|pattern| !pattern.trim().is_empty())
+            .map(String::from)
+            .collect();
+        self.settings.save(&self.settings_path);
+        // Without a daemon the next one reads the saved file at startup
+        #[cfg(unix)]
+        let _ = crate::ipc::request("reload_settings", serde_json::Value::Null);
+    }
+}
+
+impl eframe::App for ClipVaultApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_settings();
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
@@ -96,6 +213,13 @@ impl eframe::App for ClipVaultApp {
             return;
         }
 
+        // An incognito period runs out on its own
+        if let PauseState::Incognito { until } = self.ui_state.pause_state
+            && until <= chrono::Utc::now().timestamp()
+        {
+            self.ui_state.pause_state = PauseState::Recording;
+        }
+
         ctx.set_visuals(if self.darkmode {
             egui::Visuals::dark()
         } else {
@@ -104,6 +228,7 @@ impl eframe::App for ClipVaultApp {
 
         // Show the top panel
         if self.ui_state.ui_mode == UiMode::Main {
+            let tag_names: Vec<String> = self.tags.iter().map(|tag| tag.name.clone()).collect();
             egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
                 let response = TopPanel::show(
                     ui,
@@ -111,15 +236,28 @@ impl eframe::App for ClipVaultApp {
                     &mut self.ui_state.show_content,
                     &mut self.darkmode,
                     &mut self.ui_state.search_query,
-                    &mut self.ui_state.clip_limit
+                    &mut self.ui_state.search_mode,
+                    &mut self.ui_state.regex_flags,
+                    self.ui_state.search_error.as_deref(),
+                    &tag_names,
+                    &mut self.ui_state.clip_limit,
+                    &mut self.ui_state.selection_filter,
+                    self.ui_state.pause_state
                 );
 
-                // Handle clip loading changes (combines search_query_changed and clip_limit_changed)
-                if response.clip_limit_changed || response.search_query_changed {
+                // Handle clip loading changes (combines search_query_changed, clip_limit_changed and selection_filter_changed)
+                if response.clip_limit_changed || response.search_query_changed || response.selection_filter_changed {
                     self.load_clips_based_on_state();
                     self.ui_state.ui_mode = UiMode::Main;
                 }
 
+                if let Some(state) = response.pause_requested {
+                    match cli::set_pause_state(&self.db, state) {
+                        Ok(()) => self.ui_state.pause_state = state,
+                        Err(e) => eprintln!("Failed to save pause state: {}", e),
+                    }
+                }
+
                 if response.show_tags {
                     self.ui_state.ui_mode = UiMode::TagFilter;
                 }
@@ -185,6 +323,27 @@ impl eframe::App for ClipVaultApp {
                         self.reset_to_main_state();
                         self.reload_tags();
                     }
+
+                    if response.closed {
+                        self.save_settings();
+                    }
+
+                    if response.show_capture_log {
+                        self.ui_state.ui_mode = UiMode::CaptureLog;
+                        self.reload_capture_log();
+                    }
+                }
+                UiMode::CaptureLog => {
+                    let response = CaptureLogView::show(ctx, &mut self.ui_state);
+
+                    if response.clear_requested {
+                        let _ = db::clear_capture_log(&self.db);
+                        self.reload_capture_log();
+                    }
+
+                    if response.refresh_requested {
+                        self.reload_capture_log();
+                    }
                 }
             }
         });
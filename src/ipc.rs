@@ -0,0 +1,528 @@
+// Local API of the tray daemon for the GUI, the command line and editor plugins: JSON-RPC 2.0
+// over a Unix socket that only the user can open, one message per line.
+//
+//   hello {protocol?}                       -> {protocol, server}
+//   list {limit?, tag?}                     -> [clip]
+//   search {query, limit?}                  -> [clip]
+//   get {id}                                -> clip
+//   add {text, title?, note?, tags?, pin?}  -> clip
+//   copy {id, original?}                    -> {copied}
+//   pause {minutes?} | resume | status      -> {recording, paused_until, label}
+//   reload_settings                         -> {reloaded}, after the GUI saved the settings
+//   subscribe                               -> {subscribed}, then `clip_added` notifications
+//   picker                                  -> {registered}, then `show_picker` notifications
+//                                              (used by the resident quick-paste window)
+//
+// Clips have the same fields as `clipvault --json` prints.
+use std::{
+    error::Error,
+    fs,
+    io::{ self, BufRead, BufReader, Write },
+    os::unix::{ fs::{ DirBuilderExt, MetadataExt, PermissionsExt }, io::AsRawFd, net::{ UnixListener, UnixStream } },
+    path::{ Path, PathBuf },
+    sync::{ Arc, Mutex },
+    thread,
+    time::Duration,
+};
+use directories::ProjectDirs;
+use rusqlite::Connection;
+use serde::{ Deserialize, de::DeserializeOwned };
+use serde_json::{ Value, json };
+
+use crate::cli::{ copy_source, pause_status };
+use crate::clipboard;
+use crate::db;
+use crate::models::{ Clip, ClipInfo, DaemonEvent, DaemonNotifier, PauseState };
+
+// Bumped on incompatible changes; clients pass the version they speak to `hello`
+pub const PROTOCOL_VERSION: u32 = 1;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const NOT_FOUND: i64 = -32001;
+const CLIPBOARD_ERROR: i64 = -32002;
+const UNSUPPORTED_PROTOCOL: i64 = -32003;
+const PICKER_RUNNING: i64 = -32004;
+
+// A subscriber that stops reading is dropped instead of stalling the monitor
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(1);
+
+// How long `request` waits for the daemon to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// `$XDG_RUNTIME_DIR/clipvault/clipvault.sock`, or a per-user directory under /tmp
+pub fn socket_path() -> PathBuf {
+    let dir = ProjectDirs::from("com", "remysedlak", "clipvault")
+        .and_then(|dirs| dirs.runtime_dir().map(Path::to_path_buf))
+        .unwrap_or_else(|| {
+            let user = std::env::var("USER").unwrap_or_default();
+            std::env::temp_dir().join(format!("clipvault-{}", user))
+        });
+    dir.join("clipvault.sock")
+}
+
+// Anyone can create the /tmp fallback first, so the socket directory must be a real directory
+// (not a symlink) of our own. Access for others left by an earlier version is removed.
+fn check_socket_dir(dir: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() {
+        let message = format!("{} is not a directory or is a symlink, refusing to use it", dir.display());
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, message));
+    }
+    if metadata.uid() != current_uid() {
+        let message = format!("{} belongs to uid {}, not to this user, refusing to use it", dir.display(), metadata.uid());
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, message));
+    }
+    if metadata.mode() & 0o077 != 0 {
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+fn current_uid() -> u32 {
+    // getuid always succeeds
+    unsafe { libc::getuid() }
+}
+
+// The user running the process on the other end of a connection
+#[cfg(target_os = "linux")]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let mut cred = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut cred as *mut libc::ucred).cast(),
+            &mut len,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(cred.uid)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let (mut uid, mut gid) = (0, 0);
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(uid)
+}
+
+// Connections that asked for `clip_added` notifications, and the quick-paste picker waiting
+// to be shown
+#[derive(Default)]
+pub struct Subscribers {
+    streams: Mutex<Vec<Arc<Mutex<UnixStream>>>>,
+    picker: Mutex<Option<Arc<Mutex<UnixStream>>>>,
+}
+
+// What a connection listens for after its request
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Listen {
+    Clips,
+    Picker,
+}
+
+impl Subscribers {
+    fn add(&self, stream: Arc<Mutex<UnixStream>>, listen: Listen) {
+        match listen {
+            Listen::Clips => {
+                if let Ok(mut streams) = self.streams.lock() {
+                    streams.push(stream);
+                }
+            }
+            Listen::Picker => {
+                if let Ok(mut picker) = self.picker.lock() {
+                    *picker = Some(stream);
+                }
+            }
+        }
+    }
+
+    fn has_picker(&self) -> bool {
+        self.picker.lock().is_ok_and(|picker| picker.is_some())
+    }
+
+    // Ask the resident picker to show itself. False when none is running, or it went away.
+    pub fn show_picker(&self) -> bool {
+        let Ok(mut picker) = self.picker.lock() else {
+            return false;
+        };
+        let line = json!({ "jsonrpc": "2.0", "method": "show_picker" }).to_string();
+        picker.take_if(|stream| write_line(stream, &line).is_err());
+        picker.is_some()
+    }
+
+    // Tell subscribers about a clip the monitor just saved
+    pub fn clip_added(&self, conn: &Connection, clip_id: i64) {
+        let Ok(mut streams) = self.streams.lock() else {
+            return;
+        };
+        if streams.is_empty() {
+            return;
+        }
+        let clip = match load_clip(conn, clip_id) {
+            Ok(clip) => clip,
+            Err(e) => {
+                eprintln!("Failed to load clip {} for subscribers: {}", clip_id, e.message);
+                return;
+            }
+        };
+        let line = json!({ "jsonrpc": "2.0", "method": "clip_added", "params": clip }).to_string();
+        streams.retain(|stream| write_line(stream, &line).is_ok());
+    }
+}
+
+// Listen on the socket in the background. Fails if another instance is already listening.
+pub fn serve(db: Arc<Mutex<Connection>>, subscribers: Arc<Subscribers>, notify: DaemonNotifier) -> io::Result<PathBuf> {
+    let path = socket_path();
+    if let Some(dir) = path.parent() {
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+        check_socket_dir(dir)?;
+    }
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, "another ClipVault is already listening"));
+        }
+        fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                // Only reachable through a descriptor passed on by one of our own processes
+                Ok(stream) if peer_uid(&stream).ok() != Some(current_uid()) => {
+                    eprintln!("Refused IPC connection from another user");
+                }
+                Ok(stream) => {
+                    let db = db.clone();
+                    let subscribers = subscribers.clone();
+                    let notify = notify.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = handle_connection(stream, &db, &subscribers, &notify) {
+                            eprintln!("IPC connection ended: {}", e);
+                        }
+                    });
+                }
+                Err(e) => eprintln!("Failed to accept IPC connection: {}", e),
+            }
+        }
+    });
+    Ok(path)
+}
+
+// Send one request to the running daemon and return its result
+pub fn request(method: &str, params: Value) -> Result<Value, Box<dyn Error>> {
+    send_request(method, params).map(|(result, _)| result)
+}
+
+// Make a subscribing request and return the names of the notifications that follow, until
+// the daemon goes away
+pub fn listen(method: &str) -> Result<impl Iterator<Item = String>, Box<dyn Error>> {
+    let (_, reader) = send_request(method, Value::Null)?;
+    reader.get_ref().set_read_timeout(None)?;
+    Ok(reader.lines().map_while(Result::ok).filter_map(|line| {
+        let notification: Value = serde_json::from_str(&line).ok()?;
+        Some(notification.get("method")?.as_str()?.to_string())
+    }))
+}
+
+fn send_request(method: &str, params: Value) -> Result<(Value, BufReader<UnixStream>), Box<dyn Error>> {
+    let path = socket_path();
+    // Don't talk to a socket someone else put in our place
+    if let Some(dir) = path.parent() {
+        check_socket_dir(dir)?;
+    }
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    stream.write_all(format!("{}\n", request).as_bytes())?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut response: Value = serde_json::from_str(&line)?;
+    if let Some(error) = response.get("error") {
+        return Err(error["message"].as_str().unwrap_or("request failed").into());
+    }
+    Ok((response["result"].take(), reader))
+}
+
+fn handle_connection(
+    stream: UnixStream,
+    db: &Mutex<Connection>,
+    subscribers: &Subscribers,
+    notify: &DaemonNotifier,
+) -> io::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    // Shared with the subscriber list, so responses and notifications don't interleave
+    let writer = Arc::new(Mutex::new(stream));
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, listen) = handle_message(&line, db, subscribers, notify);
+        if let Some(response) = response {
+            write_line(&writer, &response.to_string())?;
+        }
+        if let Some(listen) = listen {
+            writer.lock().map_err(|_| io::Error::other("lock poisoned"))?.set_write_timeout(Some(NOTIFY_TIMEOUT))?;
+            subscribers.add(writer.clone(), listen);
+        }
+    }
+    Ok(())
+}
+
+fn write_line(stream: &Mutex<UnixStream>, line: &str) -> io::Result<()> {
+    let mut stream = stream.lock().map_err(|_| io::Error::other("lock poisoned"))?;
+    stream.write_all(format!("{}\n", line).as_bytes())
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl From<rusqlite::Error> for RpcError {
+    fn from(e: rusqlite::Error) -> Self {
+        RpcError::new(INTERNAL_ERROR, e.to_string())
+    }
+}
+
+// The response to send, if any (notifications get none), and what the connection subscribed to
+fn handle_message(
+    line: &str,
+    db: &Mutex<Connection>,
+    subscribers: &Subscribers,
+    notify: &DaemonNotifier,
+) -> (Option<Value>, Option<Listen>) {
+    let request: Request = match serde_json::from_str::<Value>(line) {
+        Err(e) => return (Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))), None),
+        Ok(value) => {
+            let id = value.get("id").cloned().unwrap_or(Value::Null);
+            match serde_json::from_value(value) {
+                Ok(request) => request,
+                Err(e) => return (Some(error_response(id, RpcError::new(INVALID_REQUEST, e.to_string()))), None),
+            }
+        }
+    };
+    let id = request.id.clone();
+    if request.jsonrpc != "2.0" {
+        let error = RpcError::new(INVALID_REQUEST, "only JSON-RPC 2.0 is supported");
+        return (Some(error_response(id.unwrap_or(Value::Null), error)), None);
+    }
+
+    let listen = match request.method.as_str() {
+        "subscribe" => Some(Listen::Clips),
+        "picker" => Some(Listen::Picker),
+        _ => None,
+    };
+    // A second picker process closes after use instead of staying around
+    let result = if listen == Some(Listen::Picker) && subscribers.has_picker() {
+        Err(RpcError::new(PICKER_RUNNING, "a picker is already running"))
+    } else {
+        match db.lock() {
+            Ok(conn) => call(&conn, &request.method, request.params, notify),
+            Err(_) => Err(RpcError::new(INTERNAL_ERROR, "database lock poisoned")),
+        }
+    };
+    let listen = listen.filter(|_| result.is_ok());
+    let response = id.map(|id| match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => error_response(id, error),
+    });
+    (response, listen)
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": error.code, "message": error.message } })
+}
+
+fn default_limit() -> usize {
+    20
+}
+
+#[derive(Deserialize)]
+struct HelloParams {
+    protocol: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    #[serde(default = "default_limit")]
+    limit: usize,
+    tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+#[derive(Deserialize)]
+struct IdParams {
+    id: i64,
+}
+
+#[derive(Deserialize)]
+struct AddParams {
+    text: String,
+    title: Option<String>,
+    note: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    pin: bool,
+}
+
+#[derive(Deserialize)]
+struct CopyParams {
+    id: i64,
+    #[serde(default)]
+    original: bool,
+}
+
+#[derive(Deserialize)]
+struct PauseParams {
+    minutes: Option<i64>,
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn call(conn: &Connection, method: &str, raw: Value, notify: &DaemonNotifier) -> Result<Value, RpcError> {
+    match method {
+        "hello" => {
+            let hello: HelloParams = params(raw)?;
+            if let Some(protocol) = hello.protocol
+                && protocol > PROTOCOL_VERSION
+            {
+                let message = format!("protocol {} requested, this ClipVault speaks {}", protocol, PROTOCOL_VERSION);
+                return Err(RpcError::new(UNSUPPORTED_PROTOCOL, message));
+            }
+            Ok(json!({ "protocol": PROTOCOL_VERSION, "server": concat!("clipvault ", env!("CARGO_PKG_VERSION")) }))
+        }
+        "list" => {
+            let list: ListParams = params(raw)?;
+            let rows = match list.tag {
+                Some(name) => {
+                    let tag_id = db::find_tag(conn, &name)?
+                        .ok_or_else(|| RpcError::new(NOT_FOUND, format!("tag {:?} not found", name)))?;
+                    db::load_clips_for_tag(conn, &tag_id)?.into_iter().take(list.limit).collect()
+                }
+                None => db::load_recent_clips(conn, list.limit)?,
+            };
+            clip_list(conn, rows)
+        }
+        "search" => {
+            let search: SearchParams = params(raw)?;
+            clip_list(conn, db::search_clips(conn, &search.query, Some(search.limit))?)
+        }
+        "get" => {
+            let get: IdParams = params(raw)?;
+            to_value(load_clip(conn, get.id)?)
+        }
+        "add" => {
+            let add: AddParams = params(raw)?;
+            if add.text.trim().is_empty() {
+                return Err(RpcError::new(INVALID_PARAMS, "nothing to add"));
+            }
+            let id = db::add_text_clip(conn, &add.text, add.title.as_deref(), add.note.as_deref(), &add.tags, add.pin)?;
+            to_value(load_clip(conn, id)?)
+        }
+        "copy" => {
+            let copy: CopyParams = params(raw)?;
+            let clip = db::load_clip(conn, copy.id)?
+                .map(Clip::from_tuple)
+                .ok_or_else(|| RpcError::new(NOT_FOUND, format!("clip {} not found", copy.id)))?;
+            let captured = copy_source(conn, clip, copy.original)
+                .map_err(|missing| RpcError::new(NOT_FOUND, format!("{} not found", missing)))?;
+            // The daemon lives on, so it can keep serving the clipboard itself
+            clipboard::copy_captured(&captured).map_err(|e| RpcError::new(CLIPBOARD_ERROR, e.to_string()))?;
+            Ok(json!({ "copied": copy.id }))
+        }
+        "pause" => {
+            let pause: PauseParams = params(raw)?;
+            let now = chrono::Utc::now().timestamp();
+            let state = match pause.minutes {
+                Some(minutes) => PauseState::checked_incognito(minutes, now).ok_or_else(|| {
+                    let message = format!("minutes must be between 1 and {}", PauseState::MAX_INCOGNITO_MINUTES);
+                    RpcError::new(INVALID_PARAMS, message)
+                })?,
+                None => PauseState::Paused,
+            };
+            db::save_pause_state(conn, state)?;
+            notify(DaemonEvent::PauseChanged(state));
+            Ok(pause_status(state))
+        }
+        "resume" => {
+            db::save_pause_state(conn, PauseState::Recording)?;
+            notify(DaemonEvent::PauseChanged(PauseState::Recording));
+            Ok(pause_status(PauseState::Recording))
+        }
+        "status" => Ok(pause_status(db::load_pause_state(conn)?)),
+        "reload_settings" => {
+            notify(DaemonEvent::SettingsChanged);
+            Ok(json!({ "reloaded": true }))
+        }
+        "subscribe" => Ok(json!({ "subscribed": true })),
+        "picker" => Ok(json!({ "registered": true })),
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method {:?}", method))),
+    }
+}
+
+fn load_clip(conn: &Connection, id: i64) -> Result<ClipInfo, RpcError> {
+    let clip = db::load_clip(conn, id)?
+        .map(Clip::from_tuple)
+        .ok_or_else(|| RpcError::new(NOT_FOUND, format!("clip {} not found", id)))?;
+    let tags = db::load_clip_tags(conn)?.remove(&id).unwrap_or_default();
+    Ok(ClipInfo::new(clip, tags))
+}
+
+fn clip_list(conn: &Connection, rows: Vec<db::ClipRow>) -> Result<Value, RpcError> {
+    let mut tags = db::load_clip_tags(conn)?;
+    let clips: Vec<ClipInfo> = rows
+        .into_iter()
+        .map(Clip::from_tuple)
+        .map(|clip| {
+            let clip_tags = tags.remove(&clip.id).unwrap_or_default();
+            ClipInfo::new(clip, clip_tags)
+        })
+        .collect();
+    to_value(clips)
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))
+}
@@ -1,7 +1,13 @@
+pub mod cli;
 pub mod clipboard;
 pub mod db;
+#[cfg(target_os = "linux")]
+pub mod dbus;
 pub mod gui;
+#[cfg(unix)]
+pub mod ipc;
 pub mod settings;
 pub mod models;
+pub mod picker;
 pub mod ui;
 pub mod utils;
\ No newline at end of file
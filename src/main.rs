@@ -1,16 +1,31 @@
 // Flag for hiding the terminal on Windows
 // #![cfg_attr(target_os = "windows", windows_subsystem = "windows")]
 
+mod cli;
 mod clipboard;
 mod db;
+#[cfg(target_os = "linux")]
+mod dbus;
 mod gui;
+#[cfg(unix)]
+mod ipc;
 mod ui;
 mod settings;
 mod models;
 mod utils;
-use std::{ error::Error, sync::{ Arc, Mutex, mpsc }, thread, path::PathBuf, env, process::Command };
-use tray_icon::{ TrayIconBuilder, menu::{ Menu, MenuEvent, MenuItem } };
-use winit::event_loop::{ ControlFlow, EventLoop };
+use std::{ error::Error, sync::{ Arc, Mutex, mpsc }, thread, process::{ self, Command }, time::{ Duration, Instant } };
+use tray_icon::{ TrayIcon, TrayIconBuilder, menu::{ Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu } };
+use global_hotkey::{ GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState, hotkey::HotKey };
+use winit::event::Event;
+use winit::event_loop::{ ControlFlow, EventLoopBuilder };
+use clap::Parser;
+use cli::Cli;
+use clipboard::{ CapturedClip, MonitorCommand, MonitorOptions };
+use clipboard::filter::{ CaptureFilter, FilterOutcome, FilterStage };
+use settings::Settings;
+use models::{ Clip, ClipKind, DaemonEvent, DaemonNotifier, PauseState, Selection };
+use utils::desktop::sibling_exe;
+use utils::images::ClipImage;
 
 // Include icon bytes from assets folder
 const ICON_BYTES: &[u8] = include_bytes!("../assets/clipboard.png");
@@ -18,7 +33,7 @@ const ICON_BYTES: &[u8] = include_bytes!("../assets/clipboard.png");
 use std::io::Cursor;
 use image::io::Reader as ImageReader;
 
-fn load_icon_embedded() -> tray_icon::Icon {
+fn load_icon_embedded(paused: bool) -> tray_icon::Icon {
     // Read image from embedded bytes (ico or png)
     let cursor = Cursor::new(ICON_BYTES);
     let image = ImageReader::new(cursor)
@@ -28,12 +43,78 @@ fn load_icon_embedded() -> tray_icon::Icon {
         .expect("Failed to decode embedded icon")
         .to_rgba8();
 
+    // Greyed out and faded while recording is paused
+    let mut image = image;
+    if paused {
+        for pixel in image.pixels_mut() {
+            let [r, g, b, a] = pixel.0;
+            let grey = ((r as u32 * 30 + g as u32 * 59 + b as u32 * 11) / 100) as u8;
+            pixel.0 = [grey, grey, grey, a / 2];
+        }
+    }
+
     let (width, height) = image.dimensions();
     let rgba = image.into_raw();
 
     tray_icon::Icon::from_rgba(rgba, width, height).expect("Failed to create icon")
 }
 
+// Delivered to the tray event loop
+enum TrayEvent {
+    Menu(MenuEvent),
+    HotKey(GlobalHotKeyEvent),
+    Daemon(DaemonEvent),
+}
+
+// Register a hotkey from its settings string; a taken or invalid key combination only disables it
+fn register_hotkey(manager: &Result<GlobalHotKeyManager, global_hotkey::Error>, spec: &str, what: &str) -> Option<HotKey> {
+    let manager = manager.as_ref().ok()?;
+    let hotkey = match spec.parse::<HotKey>() {
+        Ok(hotkey) => hotkey,
+        Err(e) => {
+            eprintln!("Invalid {} hotkey {:?}: {}", what, spec, e);
+            return None;
+        }
+    };
+    match manager.register(hotkey) {
+        Ok(()) => Some(hotkey),
+        Err(e) => {
+            eprintln!("Failed to register {} hotkey {}: {}", what, spec, e);
+            None
+        }
+    }
+}
+
+// Start the `gui` binary installed next to this one as a separate process
+fn launch_gui(args: &[&str]) {
+    match Command::new(sibling_exe("gui")).args(args).spawn() {
+        Ok(_) => println!("GUI launched successfully"),
+        Err(e) => println!("Failed to launch GUI: {}", e),
+    }
+}
+
+// Reflect the recording state in the monitor, the tray icon and the menu
+fn apply_pause_state(state: PauseState, monitor: &mpsc::Sender<MonitorCommand>, tray_icon: &TrayIcon, pause_item: &MenuItem) {
+    let command = if state.is_recording() { MonitorCommand::Resume } else { MonitorCommand::Pause };
+    let _ = monitor.send(command);
+
+    let now = chrono::Utc::now().timestamp();
+    let tooltip = if state.is_recording() {
+        "ClipVault".to_string()
+    } else {
+        format!("ClipVault ({})", state.label(now))
+    };
+    let _ = tray_icon.set_tooltip(Some(tooltip));
+    let _ = tray_icon.set_icon(Some(load_icon_embedded(!state.is_recording())));
+    pause_item.set_text(if state.is_recording() { "Pause recording" } else { "Resume recording" });
+}
+
+// The newest CLIPBOARD clip as the monitor would have captured it
+fn last_clip_for_restore(db: &rusqlite::Connection) -> Option<CapturedClip> {
+    let clip = Clip::from_tuple(db::load_last_clip(db, Selection::Clipboard).ok()??);
+    cli::captured_clip(db, &clip)
+}
+
 #[allow(dead_code)]
 #[derive(Debug)]
 enum AppEvent {
@@ -42,26 +123,190 @@ enum AppEvent {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            process::exit(if e.use_stderr() { cli::EXIT_USAGE } else { cli::EXIT_OK });
+        }
+    };
+    if let Some(command) = cli.command {
+        process::exit(cli::run(command, cli.json));
+    }
+
     // Initialize DB connection (thread safe with Mutex)
     let db = Arc::new(Mutex::new(db::init_db()?));
+    let (settings, _) = Settings::load();
+    let mut pause_state = db::load_pause_state(&db.lock().unwrap())?;
+
+    // Create event loop, menu clicks, hotkeys and changes made through the APIs are delivered to it
+    // as user events so it can sleep until one arrives
+    let event_loop = EventLoopBuilder::<TrayEvent>::with_user_event().build()?;
+    let proxy = event_loop.create_proxy();
+    MenuEvent::set_event_handler(Some(move |event| {
+        let _ = proxy.send_event(TrayEvent::Menu(event));
+    }));
+    let proxy = event_loop.create_proxy();
+    GlobalHotKeyEvent::set_event_handler(Some(move |event| {
+        let _ = proxy.send_event(TrayEvent::HotKey(event));
+    }));
+    let proxy = event_loop.create_proxy();
+    let notify: DaemonNotifier = Arc::new(move |event| {
+        let _ = proxy.send_event(TrayEvent::Daemon(event));
+    });
+
+    // Local API for the GUI, the command line and editor plugins
+    #[cfg(unix)]
+    let subscribers = Arc::new(ipc::Subscribers::default());
+    #[cfg(unix)]
+    let socket = match ipc::serve(db.clone(), subscribers.clone(), notify.clone()) {
+        Ok(path) => {
+            println!("Listening for IPC requests on {}", path.display());
+            Some(path)
+        }
+        Err(e) => {
+            eprintln!("IPC socket unavailable: {}", e);
+            None
+        }
+    };
+
+    // org.clipvault.Vault on the session bus, for desktop integrations
+    #[cfg(target_os = "linux")]
+    let dbus = match dbus::serve(db.clone(), notify.clone()) {
+        Ok(service) => {
+            println!("Exported {} on the session bus", dbus::BUS_NAME);
+            Some(service)
+        }
+        Err(e) => {
+            eprintln!("D-Bus service unavailable: {}", e);
+            None
+        }
+    };
+
+    // Replaced when the GUI saves new settings
+    let filter = Arc::new(Mutex::new(CaptureFilter::new(settings.capture_filter.clone(), settings.url_cleaning.clone())));
 
     // Start clipboard monitoring thread
-    {
+    let monitor = {
         let db = db.clone();
-        thread::spawn(move || {
-            let _ = clipboard::monitor_clipboard(move |clip, _old_timestamp| {
-                // Generate current timestamp as i64 (seconds since epoch)
-                let timestamp = chrono::Utc::now().timestamp();
+        let filter = filter.clone();
+        #[cfg(unix)]
+        let subscribers = subscribers.clone();
+        let mut options = MonitorOptions::from_settings(&settings);
+        options.start_paused = !pause_state.is_recording();
+        if settings.restore_clipboard_on_startup {
+            options.restore = last_clip_for_restore(&db.lock().unwrap());
+        }
+        let (monitor, _handle) = clipboard::monitor_clipboard(options, move |captured, origin, _old_timestamp| {
+            // Generate current timestamp as i64 (seconds since epoch)
+            let timestamp = chrono::Utc::now().timestamp();
+
+            // Pauses made without the socket, e.g. by a GUI on a platform without one, are only in the database
+            if !db::load_pause_state(&db.lock().unwrap()).map(|state| state.is_recording()).unwrap_or(true) {
+                println!("Recording is paused, dropping clip");
+                return;
+            }
 
+            let saved = match captured {
+                CapturedClip::Text { text, formats, selection } => {
+                    let outcome = filter.lock().unwrap().apply(&text);
+                    let db = db.lock().unwrap();
+                    let clip = match &outcome {
+                        FilterOutcome::Accepted { text, .. } => text.clone(),
+                        FilterOutcome::Dropped { stage, reason } => {
+                            println!("Dropped clip at {} stage: {}", stage.as_str(), reason);
+                            if let Err(e) = db::save_capture_log(&db, &outcome.log_entry(&text, timestamp, None)) {
+                                eprintln!("Failed to log dropped clip: {}", e);
+                            }
+                            return;
+                        }
+                    };
+                    match db::save_selection_clip(&db, &clip, selection, timestamp) {
+                        Ok(clip_id) => {
+                            println!("Saved clip: {}, {}", clip, timestamp);
+                            if let Err(e) = db::save_capture_log(&db, &outcome.log_entry(&text, timestamp, Some(clip_id))) {
+                                eprintln!("Failed to log saved clip: {}", e);
+                            }
+                            if outcome.changed(FilterStage::CleanUrl)
+                                && let Err(e) = db::save_clip_original(&db, clip_id, &text)
+                            {
+                                eprintln!("Failed to save original link: {}", e);
+                            }
+                            if let Err(e) = db::save_clip_formats(&db, clip_id, &formats) {
+                                eprintln!("Failed to save clip formats: {}", e);
+                            }
+                            if let Err(e) = db::save_clip_origin(&db, clip_id, &origin) {
+                                eprintln!("Failed to save clip origin: {}", e);
+                            }
+                            Some(clip_id)
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to save clip: {}", e);
+                            None
+                        }
+                    }
+                }
+                CapturedClip::Files(paths) => {
+                    let content = paths
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    let db = db.lock().unwrap();
+                    match db::save_clip_of_kind(&db, &content, ClipKind::Files, timestamp) {
+                        Ok(clip_id) => {
+                            println!("Saved files clip: {} file(s), {}", paths.len(), timestamp);
+                            if let Err(e) = db::save_clip_origin(&db, clip_id, &origin) {
+                                eprintln!("Failed to save clip origin: {}", e);
+                            }
+                            Some(clip_id)
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to save files clip: {}", e);
+                            None
+                        }
+                    }
+                }
+                CapturedClip::Image { width, height, rgba } => {
+                    // Encode before taking the lock, PNG compression is slow for big images
+                    let image = match ClipImage::from_rgba(width, height, rgba) {
+                        Ok(image) => image,
+                        Err(e) => {
+                            eprintln!("Failed to encode image clip: {}", e);
+                            return;
+                        }
+                    };
+                    let db = db.lock().unwrap();
+                    match db::save_image_clip(&db, &image, timestamp) {
+                        Ok(clip_id) => {
+                            println!("Saved image clip: {}, {}", image.label(), timestamp);
+                            if let Err(e) = db::save_clip_origin(&db, clip_id, &origin) {
+                                eprintln!("Failed to save clip origin: {}", e);
+                            }
+                            Some(clip_id)
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to save image clip: {}", e);
+                            None
+                        }
+                    }
+                }
+            };
+
+            #[cfg(unix)]
+            if let Some(clip_id) = saved {
                 let db = db.lock().unwrap();
-                if let Err(e) = db::save_clip(&db, &clip, timestamp) {
-                    eprintln!("Failed to save clip: {}", e);
-                } else {
-                    println!("Saved clip: {}, {}", clip, timestamp);
+                subscribers.clip_added(&db, clip_id);
+                #[cfg(target_os = "linux")]
+                if let Some(dbus) = &dbus {
+                    dbus.clip_added(&db, clip_id);
                 }
-            });
-        });
-    }
+            }
+            #[cfg(not(unix))]
+            let _ = saved;
+        })?;
+        monitor
+    };
 
     // Create channel for GUI communication
     let (__gui_tx, gui_rx) = mpsc::channel::<AppEvent>();
@@ -72,22 +317,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             match event {
                 AppEvent::OpenGui => {
                     println!("Opening GUI...");
-                    fn get_gui_exe_path() -> PathBuf {
-                        let mut exe_path = env
-                            ::current_exe()
-                            .expect("Failed to get current exe path");
-                        exe_path.pop(); // Remove clipvault.exe file name
-                        exe_path.push("gui.exe"); // Append gui.exe
-                        exe_path
-                    }
-
-                    // In your tray menu event handler:
-                    let gui_path = get_gui_exe_path();
-
-                    match Command::new(gui_path).spawn() {
-                        Ok(_) => println!("GUI launched successfully"),
-                        Err(e) => println!("Failed to launch GUI: {}", e),
-                    }
+                    launch_gui(&[]);
                 }
                 AppEvent::Quit => {
                     println!("Quitting application...");
@@ -97,67 +327,128 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     });
 
-    // Create event loop
-    let event_loop = EventLoop::new()?;
-
     // Create tray menu
     let tray_menu = Menu::new();
     let open_item = MenuItem::new("Open", true, None);
+    let pause_item = MenuItem::new("Pause recording", true, None);
+    let incognito_menu = Submenu::new("Incognito for", true);
+    let incognito_items = [(15, "15 minutes"), (60, "1 hour"), (240, "4 hours")]
+        .map(|(minutes, label)| (minutes, MenuItem::new(label, true, None)));
     let quit_item = MenuItem::new("Quit", true, None);
 
+    for (_, item) in &incognito_items {
+        incognito_menu.append(item)?;
+    }
     tray_menu.append(&open_item)?;
+    tray_menu.append(&PredefinedMenuItem::separator())?;
+    tray_menu.append(&pause_item)?;
+    tray_menu.append(&incognito_menu)?;
+    tray_menu.append(&PredefinedMenuItem::separator())?;
     tray_menu.append(&quit_item)?;
 
     // Load icon 
-    let icon = load_icon_embedded();
+    let icon = load_icon_embedded(false);
 
-    let _tray_icon = TrayIconBuilder::new()
+    let tray_icon = TrayIconBuilder::new()
         .with_menu(Box::new(tray_menu))
         .with_tooltip("ClipVault")
         .with_icon(icon)
         .build()?;
+    apply_pause_state(pause_state, &monitor, &tray_icon, &pause_item);
 
-    println!("Tray running. Right-click icon to open GUI or quit.");
+    // Toggle recording and open the quick-paste picker from anywhere
+    let hotkey_manager = GlobalHotKeyManager::new();
+    if let Err(e) = &hotkey_manager {
+        eprintln!("Global hotkeys unavailable: {}", e);
+    }
+    let pause_hotkey = register_hotkey(&hotkey_manager, &settings.pause_hotkey, "pause");
+    let picker_hotkey = register_hotkey(&hotkey_manager, &settings.picker_hotkey, "quick-paste");
 
-    // Get menu event receiver
-    let menu_channel = MenuEvent::receiver();
-
-    event_loop.run(move |_event, elwt| {
-        elwt.set_control_flow(ControlFlow::Wait);
-
-        // Handle menu events
-        if let Ok(event) = menu_channel.try_recv() {
-            match event.id {
-                id if id == open_item.id() => {
-                    println!("Open button clicked - launching GUI as separate process...");
-                    use std::process::Command;
-
-                    fn get_gui_exe_path() -> PathBuf {
-                        let mut exe_path = env
-                            ::current_exe()
-                            .expect("Failed to get current exe path");
-                        exe_path.pop(); // Remove clipvault.exe file name
-                        exe_path.push("gui.exe"); // Append gui.exe
-                        exe_path
-                    }
+    println!("Tray running. Right-click icon to open GUI or quit.");
 
-                    let gui_path = get_gui_exe_path();
+    event_loop.run(move |event, elwt| {
+        // Keep the manager alive, dropping it unregisters the hotkey
+        let _ = &hotkey_manager;
 
-                    match Command::new(gui_path).spawn() {
-                        Ok(_) => println!("GUI launched successfully"),
-                        Err(e) => println!("Failed to launch GUI: {}", e),
-                    }
+        let toggled = if pause_state.is_recording() { PauseState::Paused } else { PauseState::Recording };
+        let mut requested = None;
+        let now = chrono::Utc::now().timestamp();
+        if let PauseState::Incognito { until } = pause_state
+            && until <= now
+        {
+            requested = Some(PauseState::Recording);
+        }
+        match event {
+            // Saved by the socket or D-Bus handler already
+            Event::UserEvent(TrayEvent::Daemon(DaemonEvent::PauseChanged(state))) => {
+                pause_state = state;
+                apply_pause_state(state, &monitor, &tray_icon, &pause_item);
+            }
+            Event::UserEvent(TrayEvent::Daemon(DaemonEvent::SettingsChanged)) => {
+                let (settings, _) = Settings::load();
+                *filter.lock().unwrap() = CaptureFilter::new(settings.capture_filter.clone(), settings.url_cleaning.clone());
+                let _ = monitor.send(MonitorCommand::Reconfigure(MonitorOptions::from_settings(&settings)));
+            }
+            Event::UserEvent(TrayEvent::HotKey(event))
+                if event.state == HotKeyState::Pressed && pause_hotkey.is_some_and(|hotkey| hotkey.id() == event.id) =>
+            {
+                requested = Some(toggled);
+            }
+            Event::UserEvent(TrayEvent::HotKey(event))
+                if event.state == HotKeyState::Pressed && picker_hotkey.is_some_and(|hotkey| hotkey.id() == event.id) =>
+            {
+                // The picker stays around hidden after its first use
+                #[cfg(unix)]
+                let shown = subscribers.show_picker();
+                #[cfg(not(unix))]
+                let shown = false;
+                if !shown {
+                    launch_gui(&["--picker"]);
                 }
-                id if id == quit_item.id() => {
-                    println!("Quit button clicked");
-                    elwt.exit();
+            }
+            // Handle menu events
+            Event::UserEvent(TrayEvent::Menu(event)) => {
+                let incognito = incognito_items.iter().find(|(_, item)| event.id == item.id());
+                match event.id {
+                    id if id == pause_item.id() => requested = Some(toggled),
+                    _ if incognito.is_some() => {
+                        let minutes = incognito.map(|(minutes, _)| *minutes).unwrap_or_default();
+                        requested = Some(PauseState::incognito_for(minutes, chrono::Utc::now().timestamp()));
+                    }
+                    id if id == open_item.id() => {
+                        println!("Open button clicked - launching GUI as separate process...");
+                        launch_gui(&[]);
+                    }
+                    id if id == quit_item.id() => {
+                        println!("Quit button clicked");
+                        let _ = monitor.send(MonitorCommand::Stop);
+                        #[cfg(unix)]
+                        if let Some(path) = &socket {
+                            let _ = std::fs::remove_file(path);
+                        }
+                        elwt.exit();
+                    }
+                    _ => {}
                 }
-                _ => {}
             }
+            _ => {}
+        }
+
+        if let Some(state) = requested {
+            if let Err(e) = db::save_pause_state(&db.lock().unwrap(), state) {
+                eprintln!("Failed to save pause state: {}", e);
+            }
+            pause_state = state;
+            apply_pause_state(state, &monitor, &tray_icon, &pause_item);
         }
 
-        // Sleep to prevent busy waiting
-        thread::sleep(std::time::Duration::from_millis(16));
+        // Sleep until the next event, or until incognito runs out
+        elwt.set_control_flow(match pause_state {
+            PauseState::Incognito { until } => {
+                ControlFlow::WaitUntil(Instant::now() + Duration::from_secs((until - now).max(0) as u64))
+            }
+            _ => ControlFlow::Wait,
+        });
     })?;
 
     Ok(())
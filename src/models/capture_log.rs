@@ -0,0 +1,11 @@
+// One decision of the capture filter pipeline, kept so dropped clips can be looked
+// up in the debug view
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureLogEntry {
+    pub timestamp: i64,
+    pub accepted: bool,
+    pub stage: String,  // the stage that dropped the clip, or "accepted"
+    pub detail: String,
+    pub preview: String, // start of the copied text on one line
+    pub clip_id: Option<i64>, // the saved clip when accepted
+}
@@ -1,21 +1,141 @@
+use crate::db::ClipRow;
+use crate::models::ClipOrigin;
+use std::path::PathBuf;
+
+// What a clip holds; stored in the `kind` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipKind {
+    Text,
+    Image,
+    Files, // content holds one path per line
+}
+
+impl ClipKind {
+    pub fn from_db(kind: &str) -> Self {
+        match kind {
+            "image" => ClipKind::Image,
+            "files" => ClipKind::Files,
+            _ => ClipKind::Text,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClipKind::Text => "text",
+            ClipKind::Image => "image",
+            ClipKind::Files => "files",
+        }
+    }
+}
+
+// Which X11 selection a clip was copied from; stored in the `selection` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Clipboard,
+    Primary, // select-and-middle-click
+}
+
+impl Selection {
+    pub fn from_db(selection: &str) -> Self {
+        match selection {
+            "primary" => Selection::Primary,
+            _ => Selection::Clipboard,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Selection::Clipboard => "clipboard",
+            Selection::Primary => "primary",
+        }
+    }
+}
+
+// One extra representation of a clip (HTML, RTF, source URL...), keyed by MIME type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipFormat {
+    pub mime: String,
+    pub data: Vec<u8>,
+}
+
+impl ClipFormat {
+    pub fn new(mime: &str, data: Vec<u8>) -> Self {
+        Self { mime: mime.to_string(), data }
+    }
+
+    // Decode the data as text. Firefox writes its text/x-moz-url targets as UTF-16.
+    pub fn as_text(&self) -> String {
+        let looks_utf16 = self.data.len().is_multiple_of(2) && self.data.iter().skip(1).step_by(2).any(|b| *b == 0);
+        if looks_utf16 {
+            let units: Vec<u16> = self.data
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units).trim_start_matches('\u{feff}').to_string()
+        } else {
+            String::from_utf8_lossy(&self.data).into_owned()
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Clip {
     pub id: i64,
     pub content: String,
     pub timestamp: i64,
     pub pinned: bool,
+    pub title: Option<String>,
+    pub note: Option<String>,
+    pub kind: ClipKind,
+    pub thumbnail: Option<Vec<u8>>, // PNG thumbnail for image clips
+    pub has_formats: bool,          // rich formats stored in clip_formats
+    pub selection: Selection,
+    pub origin: Option<ClipOrigin>, // application/window it was copied from
+    pub original: Option<String>,   // text as copied, when tracking parameters were stripped
 }
 
 impl Clip {
     pub fn new(id: i64, content: String, timestamp: i64, pinned: bool) -> Self {
-        Self { id, content, timestamp, pinned }
+        Self {
+            id,
+            content,
+            timestamp,
+            pinned,
+            title: None,
+            note: None,
+            kind: ClipKind::Text,
+            thumbnail: None,
+            has_formats: false,
+            selection: Selection::Clipboard,
+            origin: None,
+            original: None,
+        }
     }
 
-    pub fn from_tuple(tuple: (i64, String, i64, bool)) -> Self {
-        Self::new(tuple.0, tuple.1, tuple.2, tuple.3)
+    pub fn from_tuple(tuple: ClipRow) -> Self {
+        Self {
+            title: tuple.4,
+            note: tuple.5,
+            kind: ClipKind::from_db(&tuple.6),
+            thumbnail: tuple.7,
+            has_formats: tuple.8,
+            selection: Selection::from_db(&tuple.9),
+            origin: tuple.10,
+            original: tuple.11,
+            ..Self::new(tuple.0, tuple.1, tuple.2, tuple.3)
+        }
     }
 
     pub fn is_empty(&self) -> bool {
         self.content.trim().is_empty()
     }
-}
\ No newline at end of file
+
+    pub fn is_text(&self) -> bool {
+        self.kind == ClipKind::Text
+    }
+
+    // Paths of a files clip
+    pub fn file_paths(&self) -> Vec<PathBuf> {
+        self.content.lines().filter(|l| !l.is_empty()).map(PathBuf::from).collect()
+    }
+}
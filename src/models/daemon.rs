@@ -0,0 +1,14 @@
+use std::sync::Arc;
+use super::PauseState;
+
+// Changes made through the daemon's socket or D-Bus API that the tray loop acts on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaemonEvent {
+    // Already saved, the monitor and the tray icon follow
+    PauseChanged(PauseState),
+    // The settings file was saved, the monitor and the capture filters are rebuilt from it
+    SettingsChanged,
+}
+
+// Hands an event to the tray loop and wakes it up
+pub type DaemonNotifier = Arc<dyn Fn(DaemonEvent) + Send + Sync>;
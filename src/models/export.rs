@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use crate::models::{Clip, ClipOrigin};
+
+// Bumped when the file layout changes in a way older versions can't read
+pub const EXPORT_VERSION: u32 = 1;
+
+// The whole vault as written by `clipvault export` and read back by `import`.
+// Binary data (images, rich formats) is base64 encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultExport {
+    pub version: u32,
+    pub exported_at: i64,
+    pub tags: Vec<ExportTag>,
+    pub clips: Vec<ExportClip>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportTag {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportClip {
+    // Only used to link clips to their sources within the file
+    pub id: i64,
+    pub content: String,
+    pub timestamp: i64,
+    pub pinned: bool,
+    pub sort_rank: i64,
+    pub title: Option<String>,
+    pub note: Option<String>,
+    pub kind: String,
+    pub selection: String,
+    #[serde(default)]
+    pub original: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub formats: Vec<ExportFormat>,
+    #[serde(default)]
+    pub image: Option<String>, // full-size PNG
+    #[serde(default)]
+    pub origin: Option<ClipOrigin>,
+    #[serde(default)]
+    pub sources: Vec<i64>, // clips it was combined from or split out of, in order
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportFormat {
+    pub mime: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped: usize, // already in the vault
+    pub tags_created: usize,
+}
+
+// Counts shown by `clipvault stats`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VaultStats {
+    pub clips: i64,
+    pub text: i64,
+    pub images: i64,
+    pub files: i64,
+    pub pinned: i64,
+    pub primary: i64, // copied from the PRIMARY selection
+    pub tags: i64,
+    pub oldest: Option<i64>,
+    pub newest: Option<i64>,
+    pub database_bytes: u64,
+}
+
+// A clip as handed to scripts and other programs, by `clipvault --json` and the IPC socket
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipInfo {
+    pub id: i64,
+    pub kind: &'static str,
+    pub content: String,
+    pub timestamp: i64,
+    pub pinned: bool,
+    pub title: Option<String>,
+    pub note: Option<String>,
+    pub selection: &'static str,
+    pub tags: Vec<String>,
+    pub origin: Option<ClipOrigin>,
+    pub original: Option<String>,
+}
+
+impl ClipInfo {
+    pub fn new(clip: Clip, tags: Vec<String>) -> Self {
+        Self {
+            id: clip.id,
+            kind: clip.kind.as_str(),
+            content: clip.content,
+            timestamp: clip.timestamp,
+            pinned: clip.pinned,
+            title: clip.title,
+            note: clip.note,
+            selection: clip.selection.as_str(),
+            tags,
+            origin: clip.origin,
+            original: clip.original,
+        }
+    }
+}
@@ -1,7 +1,17 @@
+pub mod capture_log;
 pub mod clip;
+pub mod daemon;
+pub mod export;
+pub mod origin;
+pub mod pause;
 pub mod tag; 
 pub mod ui_state;
 
-pub use clip::Clip;
+pub use capture_log::CaptureLogEntry;
+pub use clip::{Clip, ClipFormat, ClipKind, Selection};
+pub use daemon::{DaemonEvent, DaemonNotifier};
+pub use export::{ClipInfo, ExportClip, ExportFormat, ExportTag, ImportSummary, VaultExport, VaultStats};
+pub use origin::ClipOrigin;
+pub use pause::PauseState;
 pub use tag::Tag;
 pub use ui_state::{UiState, UiMode};
\ No newline at end of file
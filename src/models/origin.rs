@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+// Where a clip was copied from, as far as the display server can tell
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClipOrigin {
+    // WM_CLASS of the selection owner, e.g. "firefox" or "KeePassXC"
+    pub app: Option<String>,
+    // Name of the process owning the selection
+    pub process: Option<String>,
+    pub window_title: Option<String>,
+    // X11 window that owned the selection, as hex
+    pub owner: Option<String>,
+    // Machine the owning client ran on, the local host unless it was a remote X client
+    pub hostname: Option<String>,
+}
+
+impl ClipOrigin {
+    // Case-insensitive match of an exclusion entry against the app class and process name
+    pub fn matches_app(&self, name: &str) -> bool {
+        let name = name.trim();
+        !name.is_empty()
+            && [&self.app, &self.process]
+                .into_iter()
+                .flatten()
+                .any(|candidate| candidate.eq_ignore_ascii_case(name))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == ClipOrigin::default()
+    }
+
+    // The application name to show, falling back to the process name
+    pub fn app_name(&self) -> Option<&str> {
+        self.app.as_deref().or(self.process.as_deref())
+    }
+
+    // One line for the clip card, e.g. "firefox — Rust docs"
+    pub fn summary(&self) -> Option<String> {
+        match (self.app_name(), self.window_title.as_deref()) {
+            (Some(app), Some(title)) => Some(format!("{} — {}", app, title)),
+            (Some(app), None) => Some(app.to_string()),
+            (None, Some(title)) => Some(title.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    // Every known field, one per line, for the hover text
+    pub fn details(&self) -> String {
+        [
+            ("Application", &self.app),
+            ("Process", &self.process),
+            ("Window", &self.window_title),
+            ("Selection owner", &self.owner),
+            ("Host", &self.hostname),
+        ]
+            .into_iter()
+            .filter_map(|(label, value)| value.as_ref().map(|value| format!("{}: {}", label, value)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,61 @@
+// Whether new clipboard contents are being recorded. Stored in the database; the GUI and the
+// command line change it through the daemon when it runs, so the tray follows at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PauseState {
+    #[default]
+    Recording,
+    // Paused until resumed by hand
+    Paused,
+    // Paused until the given unix timestamp, then recording resumes on its own
+    Incognito { until: i64 },
+}
+
+impl PauseState {
+    // Longest incognito period a client can ask for, a year
+    pub const MAX_INCOGNITO_MINUTES: i64 = 365 * 24 * 60;
+
+    // Read the stored value, an incognito period that has run out counts as recording
+    pub fn from_db(value: Option<&str>, now: i64) -> Self {
+        match value {
+            Some("paused") => PauseState::Paused,
+            Some(until) => match until.parse::<i64>() {
+                Ok(until) if until > now => PauseState::Incognito { until },
+                _ => PauseState::Recording,
+            },
+            None => PauseState::Recording,
+        }
+    }
+
+    pub fn to_db(self) -> Option<String> {
+        match self {
+            PauseState::Recording => None,
+            PauseState::Paused => Some("paused".to_string()),
+            PauseState::Incognito { until } => Some(until.to_string()),
+        }
+    }
+
+    pub fn incognito_for(minutes: i64, now: i64) -> Self {
+        PauseState::Incognito { until: now.saturating_add(minutes.saturating_mul(60)) }
+    }
+
+    // An incognito period asked for through an API, None unless it lasts 1 to
+    // MAX_INCOGNITO_MINUTES minutes
+    pub fn checked_incognito(minutes: i64, now: i64) -> Option<Self> {
+        (1..=Self::MAX_INCOGNITO_MINUTES).contains(&minutes).then(|| Self::incognito_for(minutes, now))
+    }
+
+    pub fn is_recording(&self) -> bool {
+        *self == PauseState::Recording
+    }
+
+    pub fn label(&self, now: i64) -> String {
+        match self {
+            PauseState::Recording => "Recording".to_string(),
+            PauseState::Paused => "Paused".to_string(),
+            PauseState::Incognito { until } => {
+                let minutes = (until - now + 59) / 60;
+                format!("Incognito, {} min left", minutes.max(1))
+            }
+        }
+    }
+}
@@ -1,13 +1,24 @@
 use chrono::NaiveDate;
 use serde::{Serialize, Deserialize};
+use crate::clipboard::{BackendKind, SelectionSync};
+use crate::clipboard::filter::FilterSettings;
+use crate::models::{CaptureLogEntry, ClipFormat, PauseState, Selection};
 use crate::settings::Settings;
+use crate::utils::combine::Separator;
+use crate::utils::files::CopiedFile;
+use crate::utils::search::{RegexFlags, SearchMode};
+use crate::utils::split::SplitMode;
+use crate::utils::urls::UrlCleaning;
 use egui::Color32;
+use std::collections::HashMap;
+use std::ops::Range;
 
 #[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum UiMode {
     Main,
     TagFilter,
     Settings,
+    CaptureLog,
 }
 
 pub struct UiState {
@@ -17,15 +28,56 @@ pub struct UiState {
     pub date_filter: NaiveDate,
     pub user_input: String,
     pub search_query: String,
+    pub search_mode: SearchMode,
+    pub regex_flags: RegexFlags,
+    pub search_error: Option<String>, // why the regex search box can't be used
+    pub search_highlights: HashMap<i64, Vec<Range<usize>>>, // matched byte ranges of each listed clip's content
+    pub copied_files: HashMap<i64, Vec<CopiedFile>>, // files clips' paths, looked up once until the clips reload
+    pub selection_filter: Option<Selection>, // None shows clips from every selection
 
     pub auto_hide_clips: bool,
+    pub monitor_primary: bool,
+    pub clipboard_backend: BackendKind,
+    pub pause_state: PauseState,
+    pub excluded_apps_text: String,
+    pub coalesce_window_ms: u64,
+    pub skip_cleared_copies: bool,
+    pub selection_sync: SelectionSync,
+    pub keep_clipboard_alive: bool,
+    pub restore_clipboard_on_startup: bool,
+    pub url_cleaning: UrlCleaning,
+    pub url_rules_text: String,
+    pub capture_filter: FilterSettings,
+    pub ignore_patterns_text: String,
     pub show_content: bool,
 
+    pub capture_log: Vec<CaptureLogEntry>,
+    pub capture_log_show_accepted: bool,
+
     pub show_create_tag_popup: bool,
     pub show_create_clip_popup: bool,
     pub show_delete_confirmation: bool,
     pub show_tag_popup_for: Option<i64>,
 
+    pub merge_selection: Vec<(i64, String)>, // (clip id, content) in the order they were picked
+    pub show_combine_popup: bool,
+    pub combine_separator: Separator,
+    pub combine_custom_separator: String,
+    pub combine_prefix: String,
+
+    pub split_clip_for: Option<i64>,
+    pub split_mode: SplitMode,
+    pub split_pattern: String,
+    pub split_tag_id: Option<i64>,
+
+    pub preview_image_for: Option<i64>,
+    pub show_formats_for: Option<(i64, Vec<ClipFormat>)>, // clip id and its stored formats
+
+    pub edit_clip_for: Option<i64>,
+    pub edit_clip_title: String,
+    pub edit_clip_note: String,
+    pub edit_clip_focus_title: bool, // set when the popup opens, cleared once the title has focus
+
     pub edit_tag_name: Option<String>,   // <-- store name being edited
     pub edit_tag_color: Option<Color32>, // <-- store color being edited
     pub selected_tag_id: Option<i64>,
@@ -45,9 +97,45 @@ impl Default for UiState {
             selected_tag_id: None,
             show_delete_confirmation: false,
             search_query: String::new(),
+            search_mode: settings.search_mode,
+            regex_flags: settings.regex_flags,
+            search_error: None,
+            search_highlights: HashMap::new(),
+            copied_files: HashMap::new(),
+            selection_filter: None,
+            merge_selection: Vec::new(),
+            show_combine_popup: false,
+            combine_separator: Separator::default(),
+            combine_custom_separator: String::new(),
+            combine_prefix: String::new(),
+            split_clip_for: None,
+            split_mode: SplitMode::default(),
+            split_pattern: String::new(),
+            split_tag_id: None,
+            preview_image_for: None,
+            show_formats_for: None,
+            edit_clip_for: None,
+            edit_clip_focus_title: false,
+            edit_clip_title: String::new(),
+            edit_clip_note: String::new(),
             edit_tag_name: None,
             edit_tag_color: None,
             auto_hide_clips: settings.auto_hide_clips,
+            monitor_primary: settings.monitor_primary,
+            clipboard_backend: settings.clipboard_backend,
+            pause_state: PauseState::Recording,
+            excluded_apps_text: settings.excluded_apps.join("\n"),
+            coalesce_window_ms: settings.coalesce_window_ms,
+            skip_cleared_copies: settings.skip_cleared_copies,
+            selection_sync: settings.selection_sync,
+            keep_clipboard_alive: settings.keep_clipboard_alive,
+            restore_clipboard_on_startup: settings.restore_clipboard_on_startup,
+            url_rules_text: settings.url_cleaning.rules.iter().map(|rule| rule.to_line()).collect::<Vec<_>>().join("\n"),
+            url_cleaning: settings.url_cleaning,
+            ignore_patterns_text: settings.capture_filter.ignore_patterns.join("\n"),
+            capture_filter: settings.capture_filter,
+            capture_log: Vec::new(),
+            capture_log_show_accepted: false,
             show_create_clip_popup: false,
         }
     }
@@ -0,0 +1,295 @@
+// Quick-paste picker opened by the global hotkey (`gui --picker`): a small window near the
+// cursor that fuzzy filters recent clips. Arrows move, Enter copies and closes, Escape closes.
+// With the daemon running the process stays around hidden, and the next hotkey press shows it
+// again instead of starting a new one.
+use std::{ cmp::Reverse, error::Error, ops::Range, process::Command, sync::mpsc };
+use eframe::egui::{ self, Key, Modifiers, ViewportCommand, text::TextWrapping };
+use rusqlite::Connection;
+
+use crate::db;
+use crate::models::{ Clip, ClipKind };
+use crate::ui::components::highlight::highlighted;
+use crate::utils::desktop::{ position_near_cursor, sibling_exe };
+use crate::utils::fuzzy::{ FuzzyMatcher, byte_ranges };
+
+// How many recent clips the picker searches, and how many matches it lists
+const PICKER_CLIPS: usize = 1000;
+const PICKER_ROWS: usize = 50;
+pub const PICKER_SIZE: [f32; 2] = [520.0, 360.0];
+
+struct Entry {
+    id: i64,
+    kind: ClipKind,
+    pinned: bool,
+    text: String, // title or content on one line
+}
+
+// A listed clip: its index in `entries` and the matched byte ranges of its text
+struct Row {
+    entry: usize,
+    ranges: Vec<Range<usize>>,
+}
+
+// Sent by the thread listening to the daemon
+enum PickerRequest {
+    Show,
+    DaemonGone,
+}
+
+pub struct PickerApp {
+    db: Connection,
+    entries: Vec<Entry>,
+    query: String,
+    rows: Vec<Row>,
+    selected: usize,
+    scroll_to_selected: bool,
+    was_focused: bool,
+    error: Option<String>,
+    visible: bool,
+    // Hotkey presses forwarded by the daemon, None when the picker closes after use
+    requests: Option<mpsc::Receiver<PickerRequest>>,
+}
+
+impl PickerApp {
+    pub fn new(db: Connection, ctx: &egui::Context) -> Self {
+        let mut app = Self {
+            db,
+            entries: Vec::new(),
+            query: String::new(),
+            rows: Vec::new(),
+            selected: 0,
+            scroll_to_selected: false,
+            was_focused: false,
+            error: None,
+            visible: true,
+            requests: resident_requests(ctx),
+        };
+        app.reload();
+        app
+    }
+
+    // Start over from the newest clips with an empty search box
+    fn reload(&mut self) {
+        self.entries = db::load_recent_clips(&self.db, PICKER_CLIPS)
+            .unwrap_or_default()
+            .into_iter()
+            .map(Clip::from_tuple)
+            .map(|clip| Entry {
+                id: clip.id,
+                kind: clip.kind,
+                pinned: clip.pinned,
+                text: one_line(clip.title.as_deref().unwrap_or(&clip.content)),
+            })
+            .collect();
+        self.query.clear();
+        self.error = None;
+        self.was_focused = false;
+        self.refilter();
+    }
+
+    fn show(&mut self, ctx: &egui::Context) {
+        self.reload();
+        self.visible = true;
+        if let Some([x, y]) = position_near_cursor(PICKER_SIZE) {
+            ctx.send_viewport_cmd(ViewportCommand::OuterPosition(egui::pos2(x, y)));
+        }
+        ctx.send_viewport_cmd(ViewportCommand::Visible(true));
+        ctx.send_viewport_cmd(ViewportCommand::Focus);
+    }
+
+    // Hide until the next hotkey press, or exit when the daemon isn't keeping the picker around
+    fn dismiss(&mut self, ctx: &egui::Context) {
+        if self.requests.is_some() {
+            self.visible = false;
+            self.was_focused = false;
+            ctx.send_viewport_cmd(ViewportCommand::Visible(false));
+        } else {
+            ctx.send_viewport_cmd(ViewportCommand::Close);
+        }
+    }
+
+    // Best matches first, ties stay in history order. Only the listed rows get highlighting.
+    fn refilter(&mut self) {
+        let mut matcher = FuzzyMatcher::new(&self.query);
+        self.rows = if matcher.is_empty() {
+            (0..self.entries.len().min(PICKER_ROWS)).map(|entry| Row { entry, ranges: Vec::new() }).collect()
+        } else {
+            let mut scored: Vec<(u32, usize)> = self.entries
+                .iter()
+                .enumerate()
+                .filter_map(|(index, entry)| Some((matcher.score(&entry.text)?, index)))
+                .collect();
+            scored.sort_by_key(|&(score, _)| Reverse(score));
+            scored
+                .into_iter()
+                .take(PICKER_ROWS)
+                .map(|(_, entry)| {
+                    let text = &self.entries[entry].text;
+                    let ranges = matcher.find(text).map(|found| byte_ranges(text, &found.positions)).unwrap_or_default();
+                    Row { entry, ranges }
+                })
+                .collect()
+        };
+        self.selected = 0;
+        self.scroll_to_selected = true;
+    }
+}
+
+impl eframe::App for PickerApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let requests: Vec<PickerRequest> = self.requests.iter().flat_map(|requests| requests.try_iter()).collect();
+        for request in requests {
+            match request {
+                PickerRequest::Show => self.show(ctx),
+                // Nothing will show a hidden picker any more
+                PickerRequest::DaemonGone => {
+                    self.requests = None;
+                    if !self.visible {
+                        ctx.send_viewport_cmd(ViewportCommand::Close);
+                    }
+                }
+            }
+        }
+        if !self.visible {
+            return;
+        }
+
+        // Taken before the search box sees them, it would move its cursor or lose focus
+        let (down, up, enter, mut close) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        // Clicking anywhere else dismisses it, like a popup menu
+        match ctx.input(|i| i.viewport().focused) {
+            Some(true) => self.was_focused = true,
+            Some(false) if self.was_focused => close = true,
+            _ => {}
+        }
+        if close {
+            self.dismiss(ctx);
+            return;
+        }
+
+        if down && self.selected + 1 < self.rows.len() {
+            self.selected += 1;
+            self.scroll_to_selected = true;
+        }
+        if up && self.selected > 0 {
+            self.selected -= 1;
+            self.scroll_to_selected = true;
+        }
+        let mut chosen = enter.then_some(self.selected);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let search = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text("Type to search clips…")
+                    .desired_width(f32::INFINITY),
+            );
+            search.request_focus();
+            if search.changed() {
+                self.refilter();
+            }
+            if let Some(error) = &self.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            ui.separator();
+
+            if self.rows.is_empty() {
+                ui.weak("No matching clips");
+            }
+            egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                for (index, row) in self.rows.iter().enumerate() {
+                    let entry = &self.entries[row.entry];
+                    let response = ui.horizontal(|ui| {
+                        let icon = match (entry.pinned, entry.kind) {
+                            (true, _) => "📌",
+                            (false, ClipKind::Image) => "🖼",
+                            (false, ClipKind::Files) => "📁",
+                            (false, ClipKind::Text) => "  ",
+                        };
+                        ui.label(icon);
+                        let mut job = highlighted(ui, &entry.text, &row.ranges);
+                        job.wrap = TextWrapping::truncate_at_width(ui.available_width());
+                        ui.selectable_label(index == self.selected, job)
+                    }).inner;
+                    if self.scroll_to_selected && index == self.selected {
+                        response.scroll_to_me(None);
+                    }
+                    if response.clicked() {
+                        chosen = Some(index);
+                    }
+                }
+            });
+        });
+        self.scroll_to_selected = false;
+
+        if let Some(row) = chosen.and_then(|index| self.rows.get(index)) {
+            match copy_clip(self.entries[row.entry].id) {
+                Ok(()) => self.dismiss(ctx),
+                Err(e) => self.error = Some(format!("Couldn't copy the clip: {}", e)),
+            }
+        }
+    }
+}
+
+// Register as the daemon's picker, so hotkey presses show this process instead of starting a
+// new one. Not on Wayland, where a window can neither hide nor place itself.
+#[cfg(unix)]
+fn resident_requests(ctx: &egui::Context) -> Option<mpsc::Receiver<PickerRequest>> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return None;
+    }
+    let notifications = match crate::ipc::listen("picker") {
+        Ok(notifications) => notifications,
+        Err(e) => {
+            eprintln!("Picker closes after use: {}", e);
+            return None;
+        }
+    };
+    let (requests, received) = mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        for method in notifications {
+            if method == "show_picker" && requests.send(PickerRequest::Show).is_err() {
+                return;
+            }
+            ctx.request_repaint();
+        }
+        let _ = requests.send(PickerRequest::DaemonGone);
+        ctx.request_repaint();
+    });
+    Some(received)
+}
+
+#[cfg(not(unix))]
+fn resident_requests(_ctx: &egui::Context) -> Option<mpsc::Receiver<PickerRequest>> {
+    None
+}
+
+// The picker may exit right after copying, so the clipboard has to be served by something
+// that stays: the running daemon, or else a `clipvault copy` process
+fn copy_clip(id: i64) -> Result<(), Box<dyn Error>> {
+    #[cfg(unix)]
+    match crate::ipc::request("copy", serde_json::json!({ "id": id })) {
+        Ok(_) => return Ok(()),
+        Err(e) => eprintln!("Daemon didn't copy the clip, falling back to clipvault copy: {}", e),
+    }
+    let status = Command::new(sibling_exe("clipvault")).args(["copy", &id.to_string()]).status()?;
+    if !status.success() {
+        return Err(format!("clipvault copy failed ({})", status).into());
+    }
+    Ok(())
+}
+
+fn one_line(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(300) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line,
+    }
+}
@@ -3,13 +3,65 @@ use directories::ProjectDirs;
 use std::fs;
 use std::path::PathBuf;
 
+use crate::clipboard::{BackendKind, SelectionSync};
+use crate::clipboard::filter::FilterSettings;
 use crate::models::UiMode;
+use crate::utils::search::{RegexFlags, SearchMode};
+use crate::utils::urls::UrlCleaning;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Settings {
     pub theme: Theme,
     pub mode: UiMode,
     pub auto_hide_clips: bool, 
+    #[serde(default)]
+    pub monitor_primary: bool, // also record the X11 PRIMARY selection
+    #[serde(default)]
+    pub clipboard_backend: BackendKind,
+    #[serde(default = "default_pause_hotkey")]
+    pub pause_hotkey: String, // toggles recording, e.g. "Ctrl+Alt+P"
+    #[serde(default = "default_picker_hotkey")]
+    pub picker_hotkey: String, // opens the quick-paste picker
+    #[serde(default = "default_excluded_apps")]
+    pub excluded_apps: Vec<String>, // WM_CLASS or process names never recorded
+    #[serde(default = "default_coalesce_window_ms")]
+    pub coalesce_window_ms: u64, // clipboard writes closer together collapse into one clip
+    #[serde(default = "default_true")]
+    pub skip_cleared_copies: bool, // drop values cleared again within that window
+    #[serde(default)]
+    pub selection_sync: SelectionSync, // mirror between CLIPBOARD and PRIMARY
+    #[serde(default)]
+    pub keep_clipboard_alive: bool, // serve the last clip when the app that copied it exits
+    #[serde(default)]
+    pub restore_clipboard_on_startup: bool,
+    #[serde(default)]
+    pub url_cleaning: UrlCleaning, // tracking parameters removed from copied links
+    #[serde(default)]
+    pub capture_filter: FilterSettings,
+    #[serde(default)]
+    pub search_mode: SearchMode,
+    #[serde(default)]
+    pub regex_flags: RegexFlags,
+}
+
+fn default_excluded_apps() -> Vec<String> {
+    ["KeePassXC", "1Password", "Bitwarden"].map(String::from).to_vec()
+}
+
+fn default_coalesce_window_ms() -> u64 {
+    300
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_pause_hotkey() -> String {
+    "Ctrl+Alt+P".to_string()
+}
+
+fn default_picker_hotkey() -> String {
+    "Ctrl+Alt+V".to_string()
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
@@ -25,6 +77,20 @@ impl Default for Settings {
             theme: Theme::Light,
             mode: UiMode::Main,
             auto_hide_clips: false,
+            monitor_primary: false,
+            clipboard_backend: BackendKind::default(),
+            pause_hotkey: default_pause_hotkey(),
+            picker_hotkey: default_picker_hotkey(),
+            excluded_apps: default_excluded_apps(),
+            coalesce_window_ms: default_coalesce_window_ms(),
+            skip_cleared_copies: true,
+            selection_sync: SelectionSync::default(),
+            keep_clipboard_alive: false,
+            restore_clipboard_on_startup: false,
+            url_cleaning: UrlCleaning::default(),
+            capture_filter: FilterSettings::default(),
+            search_mode: SearchMode::default(),
+            regex_flags: RegexFlags::default(),
         }
     }
 }
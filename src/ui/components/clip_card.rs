@@ -1,12 +1,21 @@
-use crate::{models::Clip};
+use crate::{models::{Clip, ClipKind, Selection}};
+use crate::clipboard;
+use crate::ui::components::highlight::highlighted;
+use crate::ui::components::image_texture::png_texture;
+use crate::utils::files::{ CopiedFile, open_containing_folder };
+use crate::utils::formatting::format_size;
 use crate::utils::formatting::format_timestamp;
 use eframe::egui::{ self, Color32, Frame as EguiFrame, Label, Layout, RichText, Stroke, TextStyle };
-use std::{collections::HashMap};
+use std::{collections::HashMap, ops::Range};
 
 pub struct ClipCard;
 
 impl ClipCard {
     // Added `tag_colors` mapping tag name -> Color32 for showing colors
+    // `merge_position` is the clip's place in the combine selection, if picked
+    // `highlights` are byte ranges of the content matched by the search
+    // `files` are the looked up paths of a files clip
+    #[allow(clippy::too_many_arguments)]
     pub fn show(
         ui: &mut egui::Ui,
         ctx: &egui::Context,
@@ -15,6 +24,9 @@ impl ClipCard {
         darkmode: bool,
         clip_tags: &HashMap<i64, Vec<String>>,
         tag_colors: &HashMap<String, Color32>,
+        merge_position: Option<usize>,
+        highlights: &[Range<usize>],
+        files: &[CopiedFile],
     ) -> ClipCardResponse {
         let mut response = ClipCardResponse::default();
 
@@ -25,7 +37,7 @@ impl ClipCard {
         const BUTTON_SIZE: [f32; 2] = [25.0, 20.0];
 
         // Outer card frame
-        EguiFrame::new()
+        let card = EguiFrame::new()
             .corner_radius(8)
             .inner_margin(egui::Margin::symmetric(8, 8))
             .outer_margin(egui::Margin::symmetric(16, 0))
@@ -38,23 +50,60 @@ impl ClipCard {
             )
             .stroke(Stroke::new(1.0, Color32::BLACK))
             .show(ui, |ui| {
+                // Title header
+                if let Some(title) = &clip.title {
+                    ui.label(RichText::new(title).strong().text_style(TextStyle::Heading));
+                    ui.add_space(2.0);
+                }
+
                 // Content section
                 EguiFrame::new().show(ui, |ui| {
                     ui.vertical(|ui| {
-                        ui.add(
-                            Label::new(
-                                if show_content {
-                                    RichText::new(&clip.content)
-                                        .monospace()
-                                        .text_style(TextStyle::Body)
-                                        .background_color(ui.visuals().window_fill)
-                                } else {
-                                    RichText::new("Content hidden")
-                                        .monospace()
-                                        .text_style(TextStyle::Body)
+                        match (&clip.kind, &clip.thumbnail) {
+                            (ClipKind::Image, Some(thumbnail)) if show_content => {
+                                let name = format!("clip_thumbnail_{}", clip.id);
+                                if let Some(texture) = png_texture(ctx, &name, thumbnail) {
+                                    let image = ui
+                                        .add(egui::Image::new(&texture).sense(egui::Sense::click()))
+                                        .on_hover_text(format!("{} (click for full size)", clip.content));
+                                    if image.clicked() {
+                                        response.preview_requested = true;
+                                    }
+                                }
+                            }
+                            (ClipKind::Files, _) if show_content => {
+                                show_file_list(ui, files);
+                            }
+                            (ClipKind::Text, _) if show_content && !highlights.is_empty() => {
+                                let mut job = highlighted(ui, &clip.content, highlights);
+                                for section in &mut job.sections {
+                                    if section.format.background == Color32::TRANSPARENT {
+                                        section.format.background = ui.visuals().window_fill;
+                                    }
                                 }
-                            ).wrap()
-                        );
+                                ui.add(Label::new(job).wrap());
+                            }
+                            _ => {
+                                ui.add(
+                                    Label::new(
+                                        if show_content {
+                                            RichText::new(&clip.content)
+                                                .monospace()
+                                                .text_style(TextStyle::Body)
+                                                .background_color(ui.visuals().window_fill)
+                                        } else {
+                                            RichText::new("Content hidden")
+                                                .monospace()
+                                                .text_style(TextStyle::Body)
+                                        }
+                                    ).wrap()
+                                );
+                            }
+                        }
+                        if let (true, Some(note)) = (show_content, &clip.note) {
+                            ui.add_space(2.0);
+                            ui.add(Label::new(RichText::new(note).italics().weak()).wrap());
+                        }
                     });
                 });
 
@@ -109,18 +158,60 @@ impl ClipCard {
 
                 // Timestamp and action buttons
                 ui.horizontal(|ui| {
+                    // Pinned clips can be dragged onto each other to reorder them
+                    if clip.pinned {
+                        ui.dnd_drag_source(egui::Id::new(("pinned_clip_drag", clip.id)), clip.id, |ui| {
+                            ui.label("☰");
+                        })
+                            .response.on_hover_text("Drag to reorder pinned clips");
+                    }
+                    if clip.is_text() {
+                        let merge_label = match merge_position {
+                            Some(position) => format!("☑ {}", position + 1),
+                            None => "☐".to_string(),
+                        };
+                        if
+                            ui
+                                .selectable_label(merge_position.is_some(), merge_label)
+                                .on_hover_text("Select to combine with other clips")
+                                .clicked()
+                        {
+                            response.merge_toggled = true;
+                        }
+                    }
                     ui.label("🕒");
                     ui.weak(format_timestamp(clip.timestamp));
+                    if clip.selection == Selection::Primary {
+                        ui.weak("PRIMARY").on_hover_text("Copied from the PRIMARY selection");
+                    }
+                    if let Some(origin) = &clip.origin
+                        && let Some(summary) = origin.summary()
+                    {
+                        ui.weak(summary).on_hover_text(origin.details());
+                    }
 
                     ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
                         ui.set_max_width(200.0);
                         if
                             ui
                                 .add_sized(BUTTON_SIZE, egui::Button::new("📋").corner_radius(8.0))
-                                .on_hover_text("Copy this text to clipboard")
+                                .on_hover_text(if clip.is_text() { "Copy as plain text" } else { "Copy to clipboard" })
                                 .clicked()
                         {
-                            ctx.copy_text(clip.content.clone());
+                            // Images are copied by the view, which has access to the full-size data
+                            match clip.kind {
+                                ClipKind::Text => {
+                                    if let Err(e) = clipboard::copy_text(&clip.content) {
+                                        eprintln!("Failed to copy clip: {}", e);
+                                    }
+                                }
+                                ClipKind::Files => {
+                                    if let Err(e) = clipboard::copy_file_list(&clip.file_paths()) {
+                                        eprintln!("Failed to copy file list: {}", e);
+                                    }
+                                }
+                                ClipKind::Image => {}
+                            }
 
                             response.copied = true;
                         }
@@ -153,10 +244,97 @@ impl ClipCard {
                         if ui.add_sized(BUTTON_SIZE, egui::Button::new("+").corner_radius(8.0)).on_hover_text("Add tags to clip.").clicked() {
                             response.add_tag_requested = true;
                         }
+
+                        if
+                            ui
+                                .add_sized(BUTTON_SIZE, egui::Button::new("\u{270F}").corner_radius(8.0))
+                                .on_hover_text("Edit title and note")
+                                .clicked()
+                        {
+                            response.edit_details_requested = true;
+                        }
+
+                        if let Some(original) = &clip.original
+                            && ui
+                                .add_sized(BUTTON_SIZE, egui::Button::new("🔗").corner_radius(8.0))
+                                .on_hover_text(format!("Copy the original link with its tracking parameters:\n{}", original))
+                                .clicked()
+                        {
+                            if let Err(e) = clipboard::copy_text(original) {
+                                eprintln!("Failed to copy original link: {}", e);
+                            }
+                            response.copied = true;
+                        }
+
+                        if clip.has_formats {
+                            if
+                                ui
+                                    .add_sized(BUTTON_SIZE, egui::Button::new("🖹").corner_radius(8.0))
+                                    .on_hover_text("Copy with formatting")
+                                    .clicked()
+                            {
+                                response.copy_formatted_requested = true;
+                            }
+
+                            if
+                                ui
+                                    .add_sized(BUTTON_SIZE, egui::Button::new("🔍").corner_radius(8.0))
+                                    .on_hover_text("Show stored formats")
+                                    .clicked()
+                            {
+                                response.show_formats_requested = true;
+                            }
+                        }
+
+                        if
+                            clip.is_text() &&
+                            ui
+                                .add_sized(BUTTON_SIZE, egui::Button::new("✂").corner_radius(8.0))
+                                .on_hover_text("Split into separate clips")
+                                .clicked()
+                        {
+                            response.split_requested = true;
+                        }
+
+                        if clip.pinned {
+                            if
+                                ui
+                                    .add_sized(BUTTON_SIZE, egui::Button::new("⬇").corner_radius(8.0))
+                                    .on_hover_text("Move to bottom of pinned clips")
+                                    .clicked()
+                            {
+                                response.move_to_bottom = true;
+                            }
+
+                            if
+                                ui
+                                    .add_sized(BUTTON_SIZE, egui::Button::new("⬆").corner_radius(8.0))
+                                    .on_hover_text("Move to top of pinned clips")
+                                    .clicked()
+                            {
+                                response.move_to_top = true;
+                            }
+                        }
                     });
                 });
             });
 
+        // Dropping another pinned clip on this one moves it here
+        if clip.pinned {
+            if let Some(dragged_id) = card.response.dnd_release_payload::<i64>() {
+                if *dragged_id != clip.id {
+                    response.dropped_clip = Some(*dragged_id);
+                }
+            } else if card.response.dnd_hover_payload::<i64>().is_some() {
+                ui.painter().rect_stroke(
+                    card.response.rect,
+                    8,
+                    Stroke::new(2.0, ui.visuals().selection.stroke.color),
+                    egui::StrokeKind::Inside
+                );
+            }
+        }
+
         response
     }
 }
@@ -167,6 +345,51 @@ pub struct ClipCardResponse {
     pub delete_requested: bool,
     pub pin_toggled: bool,
     pub add_tag_requested: bool,
+    pub edit_details_requested: bool,
+    pub merge_toggled: bool,
+    pub split_requested: bool,
+    pub preview_requested: bool,
+    pub copy_formatted_requested: bool,
+    pub show_formats_requested: bool,
+    pub move_to_top: bool,
+    pub move_to_bottom: bool,
+    pub dropped_clip: Option<i64>, // ID of a pinned clip dropped onto this one
+}
+
+// One row per copied file: name, size (or "missing") and a button to open its folder
+fn show_file_list(ui: &mut egui::Ui, files: &[CopiedFile]) {
+    for CopiedFile { path, metadata } in files {
+        ui.horizontal(|ui| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string());
+            match metadata {
+                Some(metadata) => {
+                    ui.label(if metadata.is_dir() { "🗁" } else { "🗋" });
+                    ui.label(RichText::new(name).monospace()).on_hover_text(path.display().to_string());
+                    if metadata.is_file() {
+                        ui.weak(format_size(metadata.len()));
+                    }
+                }
+                None => {
+                    ui.label("⚠");
+                    ui.label(RichText::new(name).monospace().strikethrough())
+                        .on_hover_text(path.display().to_string());
+                    ui.weak("missing");
+                }
+            }
+            if
+                ui
+                    .small_button("📂")
+                    .on_hover_text("Open containing folder")
+                    .clicked() &&
+                let Err(e) = open_containing_folder(path)
+            {
+                eprintln!("Failed to open folder for {}: {}", path.display(), e);
+            }
+        });
+    }
 }
 
 // Helper for contrasting text color on a colored background
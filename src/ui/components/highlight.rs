@@ -0,0 +1,33 @@
+use eframe::egui::{ self, TextFormat, TextStyle, text::LayoutJob };
+use std::ops::Range;
+
+// `text` in the body font with the byte `ranges` (sorted, non-overlapping) marked, for search matches
+pub fn highlighted(ui: &egui::Ui, text: &str, ranges: &[Range<usize>]) -> LayoutJob {
+    let normal = TextFormat {
+        font_id: TextStyle::Body.resolve(ui.style()),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let marked = TextFormat {
+        background: ui.visuals().selection.bg_fill.gamma_multiply(0.5),
+        color: ui.visuals().strong_text_color(),
+        ..normal.clone()
+    };
+
+    let mut job = LayoutJob::default();
+    let mut at = 0;
+    for range in ranges.iter().filter(|range| range.end <= text.len()) {
+        if range.end <= at {
+            continue;
+        }
+        if range.start > at {
+            job.append(&text[at..range.start], 0.0, normal.clone());
+        }
+        job.append(&text[range.start.max(at)..range.end], 0.0, marked.clone());
+        at = range.end;
+    }
+    if at < text.len() {
+        job.append(&text[at..], 0.0, normal);
+    }
+    job
+}
@@ -0,0 +1,33 @@
+use crate::utils::images::decode_png;
+use eframe::egui::{self, ColorImage, TextureHandle, TextureOptions};
+
+// Decode a stored PNG into an egui texture, cached in egui's temp memory under `name`
+// so it is only decoded once per GUI session
+pub fn png_texture(ctx: &egui::Context, name: &str, png: &[u8]) -> Option<TextureHandle> {
+    if let Some(texture) = cached_texture(ctx, name) {
+        return Some(texture);
+    }
+
+    let image = match decode_png(png) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("Failed to decode image {}: {}", name, e);
+            return None;
+        }
+    };
+    let texture = ctx.load_texture(name, png_to_color_image(&image), TextureOptions::LINEAR);
+    ctx.data_mut(|d| d.insert_temp(egui::Id::new(name), texture.clone()));
+    Some(texture)
+}
+
+// A texture previously loaded with `png_texture`, if any
+pub fn cached_texture(ctx: &egui::Context, name: &str) -> Option<TextureHandle> {
+    ctx.data(|d| d.get_temp::<TextureHandle>(egui::Id::new(name)))
+}
+
+pub fn png_to_color_image(image: &image::RgbaImage) -> ColorImage {
+    ColorImage::from_rgba_unmultiplied(
+        [image.width() as usize, image.height() as usize],
+        image.as_raw(),
+    )
+}
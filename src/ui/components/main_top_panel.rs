@@ -1,23 +1,32 @@
-use eframe::egui::{ self, Layout };
+use eframe::egui::{ self, Key, Layout, Modifiers };
 use egui_extras::DatePickerButton;
 use chrono::NaiveDate;
+use crate::models::{ PauseState, Selection };
+use crate::utils::search::{ RegexFlags, SearchMode, completions };
 
 pub struct TopPanel;
 
 impl TopPanel {
+    #[allow(clippy::too_many_arguments)]
     pub fn show(
         ui: &mut egui::Ui,
         date: &mut NaiveDate,
         show_content: &mut bool,
         darkmode: &mut bool,
         search_query: &mut String,
-        clip_limit: &mut usize
+        search_mode: &mut SearchMode,
+        regex_flags: &mut RegexFlags,
+        search_error: Option<&str>,
+        tag_names: &[String],
+        clip_limit: &mut usize,
+        selection_filter: &mut Option<Selection>,
+        pause_state: PauseState
     ) -> TopPanelResponse {
         let mut response = TopPanelResponse::default();
         ui.add_space(2.0);
         ui.horizontal(|ui| {
 
-            if search_query == "" && *date == chrono::Utc::now().date_naive() {
+            if search_query == "" && *date == chrono::Utc::now().date_naive() && selection_filter.is_none() {
                 ui.heading("Recent clips");
 
             } else {
@@ -40,6 +49,29 @@ impl TopPanel {
                 *clip_limit = selected;
             }
 
+            // Filter by the selection clips were copied from (PRIMARY is X11 only)
+            if cfg!(target_os = "linux") {
+                let before = *selection_filter;
+                let label = match selection_filter {
+                    None => "All",
+                    Some(Selection::Clipboard) => "Clipboard",
+                    Some(Selection::Primary) => "Primary",
+                };
+                egui::ComboBox::from_id_salt("selection_filter")
+                    .width(80.0)
+                    .selected_text(label)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(selection_filter, None, "All");
+                        ui.selectable_value(selection_filter, Some(Selection::Clipboard), "Clipboard");
+                        ui.selectable_value(selection_filter, Some(Selection::Primary), "Primary");
+                    })
+                    .response
+                    .on_hover_text("Filter by selection");
+                if *selection_filter != before {
+                    response.selection_filter_changed = true;
+                }
+            }
+
 
             ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
                 // Show/Hide content toggle
@@ -57,6 +89,28 @@ impl TopPanel {
                     *show_content = !*show_content;
                 }
 
+                // Pause/incognito menu, the tray app stops recording within a couple of seconds
+                let now = chrono::Utc::now().timestamp();
+                let pause_label = if pause_state.is_recording() { "⏺" } else { "⏸" };
+                ui.menu_button(pause_label, |ui| {
+                    ui.label(pause_state.label(now));
+                    ui.separator();
+                    if pause_state.is_recording() {
+                        if ui.button("Pause recording").clicked() {
+                            response.pause_requested = Some(PauseState::Paused);
+                        }
+                    } else if ui.button("Resume recording").clicked() {
+                        response.pause_requested = Some(PauseState::Recording);
+                    }
+                    for (minutes, label) in [(15, "Incognito for 15 minutes"), (60, "Incognito for 1 hour")] {
+                        if ui.button(label).clicked() {
+                            response.pause_requested = Some(PauseState::incognito_for(minutes, now));
+                        }
+                    }
+                })
+                    .response
+                    .on_hover_text(pause_state.label(now));
+
                 // Tags button
                 if ui.button("🗁").on_hover_text("View tags").clicked() {
                     response.show_tags = true;
@@ -97,17 +151,112 @@ impl TopPanel {
                 }
 
                 ui.horizontal(|ui| {
+                let search_id = ui.make_persistent_id("search_box");
+                let suggestions = if *search_mode == SearchMode::Regex {
+                    Vec::new()
+                } else {
+                    completions(search_query, tag_names)
+                };
+                let mut accepted = None;
+
+                // Tab takes the first suggestion instead of moving focus
+                let focused = ui.memory(|memory| memory.has_focus(search_id));
+                if focused && !suggestions.is_empty() && ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Tab)) {
+                    accepted = Some(suggestions[0].query.clone());
+                }
+
                 let text_edit = egui::TextEdit::singleline(search_query)
+                    .id(search_id)
                     .hint_text("Search...");
                 
                 // Add text edit and get egui's response
-                let ui_response = ui.add_sized([100.0, 20.0], text_edit); 
+                let hint = if *search_mode == SearchMode::Regex {
+                    "A regular expression matched against content, title and note"
+                } else {
+                    "Words, \"exact phrases\", -excluded, OR, and tag:, pinned:, kind:, after:, before:, len:, app:, window:, host:"
+                };
+                let ui_response = ui.add_sized([160.0, 20.0], text_edit).on_hover_text(hint);
 
                 // Signal to ClipVaultApp if the search text changed
                 if ui_response.changed() {
                     response.search_query_changed = true;
                 }
 
+                // Autocomplete below the box, kept open while hovered so clicks on it land
+                let hovered_id = search_id.with("completions_hovered");
+                let hovered = ui.data(|data| data.get_temp::<bool>(hovered_id).unwrap_or(false));
+                let popup = egui::Popup::from_response(&ui_response)
+                    .open((ui_response.has_focus() || hovered) && !suggestions.is_empty())
+                    .show(|ui| {
+                        for suggestion in &suggestions {
+                            let label = if suggestion.hint.is_empty() {
+                                suggestion.label.clone()
+                            } else {
+                                format!("{}  {}", suggestion.label, suggestion.hint)
+                            };
+                            if ui.selectable_label(false, label).clicked() {
+                                accepted = Some(suggestion.query.clone());
+                            }
+                        }
+                    });
+                let hovered = popup.is_some_and(|popup| popup.response.contains_pointer());
+                ui.data_mut(|data| data.insert_temp(hovered_id, hovered));
+
+                if let Some(query) = accepted {
+                    *search_query = query;
+                    response.search_query_changed = true;
+                    ui_response.request_focus();
+                    if let Some(mut state) = egui::TextEdit::load_state(ui.ctx(), search_id) {
+                        let end = egui::text::CCursor::new(search_query.chars().count());
+                        state.cursor.set_char_range(Some(egui::text::CCursorRange::one(end)));
+                        state.store(ui.ctx(), search_id);
+                    }
+                }
+
+                let fuzzy = *search_mode == SearchMode::Fuzzy;
+                if
+                    ui
+                        .selectable_label(fuzzy, "≈")
+                        .on_hover_text("Fuzzy search: rank clips by matching letters in order, typos and abbreviations welcome")
+                        .clicked()
+                {
+                    *search_mode = if fuzzy { SearchMode::Substring } else { SearchMode::Fuzzy };
+                    response.search_query_changed = true;
+                }
+
+                let regex = *search_mode == SearchMode::Regex;
+                if
+                    ui
+                        .selectable_label(regex, ".*")
+                        .on_hover_text(r"Regex search, e.g. \b\d{1,3}(\.\d{1,3}){3}\b for IPv4 addresses")
+                        .clicked()
+                {
+                    *search_mode = if regex { SearchMode::Substring } else { SearchMode::Regex };
+                    response.search_query_changed = true;
+                }
+                if regex {
+                    if ui.selectable_label(regex_flags.ignore_case, "Aa").on_hover_text("Ignore case").clicked() {
+                        regex_flags.ignore_case = !regex_flags.ignore_case;
+                        response.search_query_changed = true;
+                    }
+                    if
+                        ui
+                            .selectable_label(regex_flags.multiline, "^$")
+                            .on_hover_text("Multiline: ^ and $ match at the start and end of every line")
+                            .clicked()
+                    {
+                        regex_flags.multiline = !regex_flags.multiline;
+                        response.search_query_changed = true;
+                    }
+                }
+
+                // regex errors end with a one-line summary, the full message points at the mistake
+                if let Some(error) = search_error {
+                    let summary = error.lines().last().unwrap_or(error);
+                    ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {}", summary))
+                        .on_hover_text(egui::RichText::new(error).monospace());
+                }
+
             });
 
             });
@@ -127,4 +276,6 @@ pub struct TopPanelResponse {
     pub search_query_changed: bool,
     pub add_clip: bool,
     pub clip_limit_changed: bool,
+    pub selection_filter_changed: bool,
+    pub pause_requested: Option<PauseState>,
 }
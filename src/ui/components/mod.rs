@@ -1,3 +1,5 @@
 pub mod clip_card;
+pub mod highlight;
+pub mod image_texture;
 pub mod main_top_panel;
 pub mod tag_card;
\ No newline at end of file
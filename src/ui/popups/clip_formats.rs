@@ -0,0 +1,57 @@
+use crate::clipboard;
+use crate::models::UiState;
+use eframe::egui::{self, RichText, TextEdit, TextStyle};
+
+pub struct ClipFormatsPopup;
+
+impl ClipFormatsPopup {
+    pub fn show(ctx: &egui::Context, ui_state: &mut UiState, plain: &str) {
+        let Some((_, formats)) = &ui_state.show_formats_for else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new("Stored Formats")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([480.0, 360.0])
+            .show(ctx, |ui| {
+                if
+                    ui.button("🖹 Copy with formatting").clicked() &&
+                    let Err(e) = clipboard::copy_with_formatting(plain, formats)
+                {
+                    eprintln!("Failed to copy with formatting: {}", e);
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for format in formats {
+                        ui.label(
+                            RichText::new(format!("{} ({} bytes)", format.mime, format.data.len()))
+                                .text_style(TextStyle::Body)
+                                .strong()
+                        );
+                        // Never show captured HTML as-is, strip scripts, handlers and the like first
+                        let mut preview = if format.mime == "text/html" {
+                            ammonia::clean(&format.as_text())
+                        } else {
+                            format.as_text()
+                        };
+                        ui.add(
+                            TextEdit::multiline(&mut preview)
+                                .interactive(false)
+                                .code_editor()
+                                .desired_rows(3)
+                                .desired_width(f32::INFINITY)
+                        );
+                        ui.add_space(8.0);
+                    }
+                });
+            });
+
+        if !open {
+            ui_state.show_formats_for = None;
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::clipboard;
+use crate::db;
+use crate::models::{Clip, UiState};
+use crate::utils::combine::{combine_clips, Separator};
+use eframe::egui::{self, RichText, TextEdit, TextStyle};
+use rusqlite::Connection;
+
+pub struct CombineClipsPopup;
+
+impl CombineClipsPopup {
+    pub fn show(ctx: &egui::Context, ui_state: &mut UiState, db: &Connection, clips: &mut Vec<Clip>) {
+        egui::Window::new("Combine Clips")
+            .collapsible(false)
+            .resizable(true)
+            .min_width(360.0)
+            .show(ctx, |ui| {
+                ui.label(RichText::new("Clips, in order:").text_style(TextStyle::Body).strong());
+                ui.add_space(4.0);
+
+                // Reorder or drop items from the selection
+                let mut move_up: Option<usize> = None;
+                let mut remove: Option<usize> = None;
+                egui::ScrollArea::vertical()
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        for (index, (_, content)) in ui_state.merge_selection.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("✖").on_hover_text("Remove from selection").clicked() {
+                                    remove = Some(index);
+                                }
+                                if index > 0 && ui.small_button("⬆").on_hover_text("Move up").clicked() {
+                                    move_up = Some(index);
+                                }
+                                let preview = content.lines().next().unwrap_or_default();
+                                ui.label(RichText::new(format!("{}. {}", index + 1, preview)).monospace());
+                            });
+                        }
+                    });
+                if let Some(index) = move_up {
+                    ui_state.merge_selection.swap(index - 1, index);
+                }
+                if let Some(index) = remove {
+                    ui_state.merge_selection.remove(index);
+                }
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    ui.label("Separator:");
+                    egui::ComboBox::from_id_salt("combine_separator")
+                        .selected_text(ui_state.combine_separator.label())
+                        .show_ui(ui, |ui| {
+                            for separator in Separator::ALL {
+                                ui.selectable_value(&mut ui_state.combine_separator, separator, separator.label());
+                            }
+                        });
+                    if ui_state.combine_separator == Separator::Custom {
+                        ui.add(TextEdit::singleline(&mut ui_state.combine_custom_separator).desired_width(80.0));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Prefix:");
+                    ui.add(
+                        TextEdit::singleline(&mut ui_state.combine_prefix)
+                            .hint_text("e.g. \"- \"")
+                            .desired_width(80.0)
+                    );
+                });
+
+                let parts: Vec<&str> = ui_state.merge_selection
+                    .iter()
+                    .map(|(_, content)| content.as_str())
+                    .collect();
+                let separator = ui_state.combine_separator.as_str(&ui_state.combine_custom_separator);
+                let mut combined = combine_clips(&parts, separator, &ui_state.combine_prefix);
+
+                ui.add_space(8.0);
+                ui.label(RichText::new("Preview:").text_style(TextStyle::Body).strong());
+                egui::ScrollArea::vertical()
+                    .id_salt("combine_preview")
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            TextEdit::multiline(&mut combined)
+                                .interactive(false)
+                                .code_editor()
+                                .desired_width(f32::INFINITY)
+                        );
+                    });
+
+                ui.add_space(8.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let has_parts = !ui_state.merge_selection.is_empty();
+                    if ui.add_enabled(has_parts, egui::Button::new("📋 Copy")).clicked() {
+                        if let Err(e) = clipboard::copy_text(&combined) {
+                            eprintln!("Failed to copy combined clips: {}", e);
+                        }
+                        Self::close(ui_state);
+                    }
+                    if ui.add_enabled(has_parts, egui::Button::new("Save as clip")).clicked() {
+                        Self::save_combined(ui_state, db, clips, &combined);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        ui_state.show_combine_popup = false;
+                    }
+                });
+            });
+    }
+
+    fn save_combined(ui_state: &mut UiState, db: &Connection, clips: &mut Vec<Clip>, combined: &str) {
+        let timestamp = chrono::Utc::now().timestamp();
+        let source_ids: Vec<i64> = ui_state.merge_selection.iter().map(|(id, _)| *id).collect();
+        match db::save_clip(db, combined, timestamp) {
+            Ok(clip_id) => {
+                if let Err(e) = db::save_clip_sources(db, clip_id, &source_ids) {
+                    eprintln!("Failed to record clip sources: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to save combined clip: {}", e),
+        }
+        Self::close(ui_state);
+        *clips = db::load_recent_clips(db, 20)
+            .unwrap_or_default()
+            .into_iter()
+            .map(Clip::from_tuple)
+            .collect();
+    }
+
+    fn close(ui_state: &mut UiState) {
+        ui_state.show_combine_popup = false;
+        ui_state.merge_selection.clear();
+    }
+}
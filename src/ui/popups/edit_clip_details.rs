@@ -0,0 +1,75 @@
+use crate::db;
+use crate::models::{Clip, UiState};
+use eframe::egui::{self, RichText, TextEdit, TextStyle};
+use rusqlite::Connection;
+
+pub struct EditClipDetailsPopup;
+
+impl EditClipDetailsPopup {
+    pub fn show(ctx: &egui::Context, ui_state: &mut UiState, db: &Connection, clips: &mut [Clip]) {
+        egui::Window::new("Edit Clip")
+            .collapsible(false)
+            .resizable(false)
+            .min_width(320.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Title:").text_style(TextStyle::Body).strong());
+
+                    let response = ui.add(
+                        TextEdit::singleline(&mut ui_state.edit_clip_title)
+                            .id(ui.make_persistent_id("edit_clip_title_input"))
+                            .hint_text("Optional title")
+                            .desired_width(240.0)
+                    );
+
+                    // Only when the popup opens, so the note can be clicked into afterwards
+                    if std::mem::take(&mut ui_state.edit_clip_focus_title) {
+                        response.request_focus();
+                    }
+                });
+
+                ui.add_space(10.0);
+                ui.label(RichText::new("Note:").text_style(TextStyle::Body).strong());
+                ui.add(
+                    TextEdit::multiline(&mut ui_state.edit_clip_note)
+                        .hint_text("Free-form note, included in search")
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(4)
+                );
+
+                ui.add_space(16.0);
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        Self::close(ui_state);
+                    }
+                    if ui.button("Save").clicked() {
+                        Self::submit_edit(ui_state, db, clips);
+                    }
+                });
+            });
+    }
+
+    fn submit_edit(ui_state: &mut UiState, db: &Connection, clips: &mut [Clip]) {
+        if let Some(clip_id) = ui_state.edit_clip_for {
+            match db::update_clip_details(db, clip_id, &ui_state.edit_clip_title, &ui_state.edit_clip_note) {
+                Ok(_) => {
+                    // Update the loaded clip in place so the current filter is kept
+                    if let Some(clip) = clips.iter_mut().find(|c| c.id == clip_id) {
+                        clip.title = Some(ui_state.edit_clip_title.trim().to_string()).filter(|t| !t.is_empty());
+                        clip.note = Some(ui_state.edit_clip_note.trim().to_string()).filter(|n| !n.is_empty());
+                    }
+                }
+                Err(e) => eprintln!("Failed to update clip details: {}", e),
+            }
+        }
+        Self::close(ui_state);
+    }
+
+    fn close(ui_state: &mut UiState) {
+        ui_state.edit_clip_for = None;
+        ui_state.edit_clip_title.clear();
+        ui_state.edit_clip_note.clear();
+    }
+}
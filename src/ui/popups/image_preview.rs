@@ -0,0 +1,70 @@
+use crate::clipboard::{self, ImageData};
+use crate::db;
+use crate::models::UiState;
+use crate::ui::components::image_texture::{cached_texture, png_texture};
+use crate::utils::images::decode_png;
+use eframe::egui;
+use rusqlite::Connection;
+
+pub struct ImagePreviewPopup;
+
+impl ImagePreviewPopup {
+    pub fn show(ctx: &egui::Context, ui_state: &mut UiState, db: &Connection) {
+        let Some(clip_id) = ui_state.preview_image_for else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new("Image Preview")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([640.0, 480.0])
+            .show(ctx, |ui| {
+                // Only hit the database until the full image has been decoded once
+                let name = format!("clip_image_{}", clip_id);
+                let texture = match cached_texture(ctx, &name) {
+                    Some(texture) => Ok(Some(texture)),
+                    None => db::load_clip_image(db, clip_id).map(|png| png_texture(ctx, &name, &png)),
+                };
+                match texture {
+                    Ok(texture) => {
+                        if let Some(texture) = texture {
+                            ui.horizontal(|ui| {
+                                let [width, height] = texture.size();
+                                ui.weak(format!("{}×{}", width, height));
+                                if ui.button("📋 Copy").on_hover_text("Copy image to clipboard").clicked() {
+                                    copy_image_clip(db, clip_id);
+                                }
+                            });
+                            ui.separator();
+                            egui::ScrollArea::both().show(ui, |ui| {
+                                ui.image(&texture);
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        ui.label(format!("Failed to load image: {}", e));
+                    }
+                }
+            });
+
+        if !open {
+            ui_state.preview_image_for = None;
+        }
+    }
+}
+
+// Put the full-size image of an image clip on the clipboard
+pub fn copy_image_clip(db: &Connection, clip_id: i64) {
+    match db::load_clip_image(db, clip_id).map(|png| decode_png(&png)) {
+        Ok(Ok(image)) => {
+            let image = ImageData { width: image.width(), height: image.height(), rgba: image.into_raw() };
+            if let Err(e) = clipboard::copy_image(&image) {
+                eprintln!("Failed to copy image clip {}: {}", clip_id, e);
+            }
+        }
+        Ok(Err(e)) => eprintln!("Failed to decode image clip {}: {}", clip_id, e),
+        Err(e) => eprintln!("Failed to load image clip {}: {}", clip_id, e),
+    }
+}
@@ -2,4 +2,9 @@ pub mod tag_assignment;
 pub mod create_tag;
 pub mod edit_tag_name;
 
-pub mod create_clip;
\ No newline at end of file
+pub mod create_clip;
+pub mod combine_clips;
+pub mod split_clip;
+pub mod image_preview;
+pub mod clip_formats;
+pub mod edit_clip_details;
\ No newline at end of file
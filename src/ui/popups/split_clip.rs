@@ -0,0 +1,116 @@
+use crate::db;
+use crate::models::{Clip, UiState};
+use crate::utils::split::{split_clip, SplitMode};
+use eframe::egui::{self, Color32, RichText, TextEdit, TextStyle};
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+pub struct SplitClipPopup;
+
+impl SplitClipPopup {
+    pub fn show(
+        ctx: &egui::Context,
+        ui_state: &mut UiState,
+        db: &Connection,
+        clips: &mut Vec<Clip>,
+        clip_tags: &mut HashMap<i64, Vec<String>>,
+        tags: &[(i64, String, Option<String>)],
+    ) {
+        let Some(clip_id) = ui_state.split_clip_for else {
+            return;
+        };
+        let Some(content) = clips.iter().find(|c| c.id == clip_id).map(|c| c.content.clone()) else {
+            ui_state.split_clip_for = None;
+            return;
+        };
+
+        egui::Window::new("Split Clip")
+            .collapsible(false)
+            .resizable(false)
+            .min_width(340.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Split:");
+                    egui::ComboBox::from_id_salt("split_mode")
+                        .selected_text(ui_state.split_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in SplitMode::ALL {
+                                ui.selectable_value(&mut ui_state.split_mode, mode, mode.label());
+                            }
+                        });
+                    let hint = match ui_state.split_mode {
+                        SplitMode::Lines => None,
+                        SplitMode::Delimiter => Some("e.g. ,"),
+                        SplitMode::Regex => Some(r"e.g. \d+\.\d+\.\d+\.\d+"),
+                    };
+                    if let Some(hint) = hint {
+                        ui.add(
+                            TextEdit::singleline(&mut ui_state.split_pattern)
+                                .hint_text(hint)
+                                .desired_width(140.0)
+                        );
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Also tag with:");
+                    let selected = tags
+                        .iter()
+                        .find(|(id, _, _)| Some(*id) == ui_state.split_tag_id)
+                        .map(|(_, name, _)| name.as_str())
+                        .unwrap_or("None");
+                    egui::ComboBox::from_id_salt("split_tag")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut ui_state.split_tag_id, None, "None");
+                            for (tag_id, tag_name, _) in tags {
+                                ui.selectable_value(&mut ui_state.split_tag_id, Some(*tag_id), tag_name);
+                            }
+                        });
+                });
+
+                ui.add_space(8.0);
+                let pieces = split_clip(&content, ui_state.split_mode, &ui_state.split_pattern);
+                match &pieces {
+                    Ok(pieces) => {
+                        ui.label(RichText::new(format!("{} new clip(s):", pieces.len())).text_style(TextStyle::Body).strong());
+                        egui::ScrollArea::vertical()
+                            .max_height(160.0)
+                            .show(ui, |ui| {
+                                for piece in pieces {
+                                    ui.label(RichText::new(piece).monospace());
+                                }
+                            });
+                    }
+                    Err(e) => {
+                        ui.label(RichText::new(format!("Invalid pattern: {}", e)).color(Color32::RED));
+                    }
+                }
+
+                ui.add_space(8.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let can_split = matches!(&pieces, Ok(p) if !p.is_empty());
+                    if ui.add_enabled(can_split, egui::Button::new("Split")).clicked() {
+                        if let Ok(pieces) = &pieces {
+                            match db::save_split_clips(db, clip_id, pieces, ui_state.split_tag_id) {
+                                Ok(_) => {
+                                    *clip_tags = db::load_clip_tags(db).unwrap_or_default();
+                                    *clips = db::load_recent_clips(db, 20)
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .map(Clip::from_tuple)
+                                        .collect();
+                                }
+                                Err(e) => eprintln!("Failed to split clip: {}", e),
+                            }
+                        }
+                        ui_state.split_clip_for = None;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        ui_state.split_clip_for = None;
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,84 @@
+use crate::models::{ UiState, UiMode };
+use crate::utils::formatting::format_timestamp;
+use eframe::egui::{ self, Layout, TopBottomPanel, CentralPanel, Color32, RichText };
+
+pub struct CaptureLogView;
+
+impl CaptureLogView {
+    pub fn show(ctx: &egui::Context, ui_state: &mut UiState) -> CaptureLogResponse {
+        let mut response = CaptureLogResponse::default();
+
+        TopBottomPanel::top("capture_log_top_panel")
+            .min_height(25.0)
+            .show(ctx, |ui| {
+                ui.add_space(2.0);
+                ui.horizontal(|ui| {
+                    ui.heading("Capture Filter Log");
+
+                    if ui.checkbox(&mut ui_state.capture_log_show_accepted, "Show accepted clips").changed() {
+                        response.refresh_requested = true;
+                    }
+                    if ui.button("🔄").on_hover_text("Reload the log").clicked() {
+                        response.refresh_requested = true;
+                    }
+                    if ui.button("Clear").on_hover_text("Forget all logged decisions").clicked() {
+                        response.clear_requested = true;
+                    }
+
+                    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                        if
+                            ui
+                                .add_sized([60.0, 20.0], egui::Button::new("Back to Settings..."))
+                                .on_hover_text("Return to settings")
+                                .clicked()
+                        {
+                            ui_state.ui_mode = UiMode::Settings;
+                        }
+                    });
+                });
+                ui.add_space(2.0);
+            });
+
+        CentralPanel::default().show(ctx, |ui| {
+            if ui_state.capture_log.is_empty() {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(40.0);
+                    ui.label(RichText::new("No clips have been dropped recently.").color(Color32::GRAY).italics());
+                });
+                return;
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid
+                    ::new("capture_log_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .spacing([16.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.strong("Time");
+                        ui.strong("Stage");
+                        ui.strong("Detail");
+                        ui.strong("Copied text");
+                        ui.end_row();
+
+                        for entry in &ui_state.capture_log {
+                            ui.weak(format_timestamp(entry.timestamp));
+                            let color = if entry.accepted { Color32::from_rgb(34, 197, 94) } else { Color32::from_rgb(239, 68, 68) };
+                            ui.label(RichText::new(&entry.stage).color(color).monospace());
+                            ui.label(&entry.detail);
+                            ui.label(RichText::new(&entry.preview).monospace());
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+
+        response
+    }
+}
+
+#[derive(Default)]
+pub struct CaptureLogResponse {
+    pub refresh_requested: bool,
+    pub clear_requested: bool,
+}
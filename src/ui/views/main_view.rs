@@ -1,11 +1,18 @@
+use crate::clipboard;
 use crate::db;
-use crate::models::{ Clip, UiState };
+use crate::models::{ Clip, ClipKind, UiState };
 use crate::ui::components::clip_card::{ ClipCard };
 use crate::ui::popups::tag_assignment::TagAssignmentPopup;
 use crate::ui::popups::create_clip::CreateClip;
+use crate::ui::popups::combine_clips::CombineClipsPopup;
+use crate::ui::popups::split_clip::SplitClipPopup;
+use crate::ui::popups::image_preview::{copy_image_clip, ImagePreviewPopup};
+use crate::ui::popups::clip_formats::ClipFormatsPopup;
+use crate::ui::popups::edit_clip_details::EditClipDetailsPopup;
 use eframe::egui::{ self, Color32, RichText, TextStyle };
 use rusqlite::Connection;
 use std::collections::HashMap;
+use crate::utils::files::stat_files;
 use crate::utils::formatting::hex_to_color32;
 
 pub struct MainView;
@@ -28,11 +35,32 @@ impl MainView {
                 ui.add_space(6.0);
                 let mut deleted_id: Option<i64> = None;
                 let mut pinned_id: Option<i64> = None;
+                let mut moved: Option<(i64, usize)> = None; // (clip id, new pinned position)
 
                 if ui_state.show_create_clip_popup {
                     CreateClip::show(ctx, ui_state, db, clips);
                 }
 
+                if ui_state.show_combine_popup {
+                    CombineClipsPopup::show(ctx, ui_state, db, clips);
+                }
+
+                // Selection bar for combining clips
+                if !ui_state.merge_selection.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.add_space(16.0);
+                        ui.label(format!("{} clip(s) selected", ui_state.merge_selection.len()));
+                        if ui.button("Combine...").on_hover_text("Join the selected clips into one").clicked() {
+                            ui_state.show_combine_popup = true;
+                        }
+                        if ui.button("Clear").on_hover_text("Clear the selection").clicked() {
+                            ui_state.merge_selection.clear();
+                            ui_state.show_combine_popup = false;
+                        }
+                    });
+                    ui.add_space(6.0);
+                }
+
                 if clips.is_empty() {
                     ui.centered_and_justified(|ui| {
                         ui.label(
@@ -59,6 +87,10 @@ impl MainView {
                         })
                         .collect();
 
+                    if clip.kind == ClipKind::Files {
+                        ui_state.copied_files.entry(clip.id).or_insert_with(|| stat_files(clip.file_paths()));
+                    }
+
                     let response = ClipCard::show(
                         ui,
                         ctx,
@@ -67,14 +99,66 @@ impl MainView {
                         darkmode,
                         clip_tags,
                         &tag_colors,
+                        ui_state.merge_selection.iter().position(|(id, _)| *id == clip.id),
+                        ui_state.search_highlights.get(&clip.id).map_or(&[], Vec::as_slice),
+                        ui_state.copied_files.get(&clip.id).map_or(&[], Vec::as_slice),
                     );
 
+                    if response.merge_toggled {
+                        match ui_state.merge_selection.iter().position(|(id, _)| *id == clip.id) {
+                            Some(index) => {
+                                ui_state.merge_selection.remove(index);
+                            }
+                            None => ui_state.merge_selection.push((clip.id, clip.content.clone())),
+                        }
+                    }
+
                     if response.delete_requested {
                         deleted_id = Some(clip.id);
                     }
                     if response.pin_toggled {
                         pinned_id = Some(clip.id);
                     }
+                    if response.move_to_top {
+                        moved = Some((clip.id, 0));
+                    }
+                    if response.move_to_bottom {
+                        moved = Some((clip.id, usize::MAX));
+                    }
+                    if let Some(dragged_id) = response.dropped_clip {
+                        let pinned_ids = db::load_pinned_clip_ids(db).unwrap_or_default();
+                        if let Some(position) = pinned_ids.iter().position(|&id| id == clip.id) {
+                            moved = Some((dragged_id, position));
+                        }
+                    }
+                    if response.edit_details_requested {
+                        ui_state.edit_clip_for = Some(clip.id);
+                        ui_state.edit_clip_title = clip.title.clone().unwrap_or_default();
+                        ui_state.edit_clip_note = clip.note.clone().unwrap_or_default();
+                        ui_state.edit_clip_focus_title = true;
+                    }
+                    if response.copied && clip.kind == ClipKind::Image {
+                        copy_image_clip(db, clip.id);
+                    }
+                    if response.copy_formatted_requested {
+                        let formats = db::load_clip_formats(db, clip.id).unwrap_or_default();
+                        if let Err(e) = clipboard::copy_with_formatting(&clip.content, &formats) {
+                            eprintln!("Failed to copy with formatting: {}", e);
+                        }
+                    }
+                    if response.show_formats_requested {
+                        match db::load_clip_formats(db, clip.id) {
+                            Ok(formats) => ui_state.show_formats_for = Some((clip.id, formats)),
+                            Err(e) => eprintln!("Failed to load clip formats: {}", e),
+                        }
+                    }
+                    if response.preview_requested {
+                        ui_state.preview_image_for = Some(clip.id);
+                    }
+                    if response.split_requested {
+                        ui_state.split_clip_for = Some(clip.id);
+                        ui_state.split_tag_id = None;
+                    }
                     if response.add_tag_requested {
                         ui_state.show_tag_popup_for = Some(clip.id);
                         ui_state.selected_tag_id = None;
@@ -82,11 +166,36 @@ impl MainView {
 
                     ui.add_space(6.0);
 
-                    if deleted_id.is_some() || pinned_id.is_some() {
+                    if deleted_id.is_some() || pinned_id.is_some() || moved.is_some() {
                         break;
                     }
                 }
 
+                // Handle title/note editing popup
+                if ui_state.edit_clip_for.is_some() {
+                    EditClipDetailsPopup::show(ctx, ui_state, db, clips);
+                }
+
+                // Handle full-size image preview
+                if ui_state.preview_image_for.is_some() {
+                    ImagePreviewPopup::show(ctx, ui_state, db);
+                }
+
+                // Handle stored formats popup
+                if let Some((clip_id, _)) = &ui_state.show_formats_for {
+                    let plain = clips
+                        .iter()
+                        .find(|c| c.id == *clip_id)
+                        .map(|c| c.content.clone())
+                        .unwrap_or_default();
+                    ClipFormatsPopup::show(ctx, ui_state, &plain);
+                }
+
+                // Handle split popup
+                if ui_state.split_clip_for.is_some() {
+                    SplitClipPopup::show(ctx, ui_state, db, clips, clip_tags, tags);
+                }
+
                 // Handle tag assignment popup
                 if let Some(clip_id) = ui_state.show_tag_popup_for {
                     TagAssignmentPopup::show(ctx, clip_id, ui_state, db, clip_tags, tags);
@@ -112,6 +221,18 @@ impl MainView {
                         .map(Clip::from_tuple)
                         .collect();
                 }
+
+                if let Some((id, position)) = moved {
+                    if let Err(e) = db::move_pinned_clip(db, id, position) {
+                        eprintln!("Failed to reorder pinned clip: {}", e);
+                    }
+                    *clips = db
+                        ::load_recent_clips(db, 20)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(Clip::from_tuple)
+                        .collect();
+                }
             });
     }
 }
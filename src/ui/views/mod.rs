@@ -1,3 +1,4 @@
 pub mod main_view;
 pub mod tag_filter_view;
-pub mod settings_view;
\ No newline at end of file
+pub mod settings_view;
+pub mod capture_log_view;
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::clipboard::{ BackendKind, SelectionSync };
 use crate::models::{ UiState, UiMode };
 use eframe::egui::{ self, Color32, RichText, Layout, TopBottomPanel, CentralPanel, CornerRadius, Vec2 };
 
@@ -31,6 +32,7 @@ impl SettingsView {
 
                         if back_button.on_hover_text("Return to main view").clicked() {
                             ui_state.ui_mode = UiMode::Main;
+                            response.closed = true;
                         }
                     });
                 });
@@ -53,6 +55,71 @@ impl SettingsView {
                             ui.vertical_centered(|ui| {
                                 
                                 ui.checkbox(&mut ui_state.auto_hide_clips, "Hide clips on startup");
+                                if cfg!(target_os = "linux") {
+                                    ui.checkbox(&mut ui_state.monitor_primary, "Record PRIMARY selection")
+                                        .on_hover_text("Also save select-and-middle-click text.");
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.label("Clipboard backend");
+                                    egui::ComboBox
+                                        ::from_id_salt("clipboard_backend")
+                                        .selected_text(ui_state.clipboard_backend.label())
+                                        .show_ui(ui, |ui| {
+                                            for kind in BackendKind::ALL {
+                                                ui.selectable_value(&mut ui_state.clipboard_backend, kind, kind.label());
+                                            }
+                                        });
+                                }).response.on_hover_text("How the clipboard is read and written. Takes effect when ClipVault restarts.");
+                                if cfg!(target_os = "linux") {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Sync selection and clipboard");
+                                        egui::ComboBox
+                                            ::from_id_salt("selection_sync")
+                                            .selected_text(ui_state.selection_sync.label())
+                                            .show_ui(ui, |ui| {
+                                                for sync in SelectionSync::ALL {
+                                                    ui.selectable_value(&mut ui_state.selection_sync, sync, sync.label());
+                                                }
+                                            });
+                                    }).response.on_hover_text("Paste selected text with Ctrl+V, or copied text with a middle click. Mirrored values aren't recorded twice.");
+                                    ui.checkbox(&mut ui_state.keep_clipboard_alive, "Keep the clipboard after apps close")
+                                        .on_hover_text("Serve the last clip when the application it was copied from exits.");
+                                    ui.checkbox(&mut ui_state.restore_clipboard_on_startup, "Restore the last clip on startup")
+                                        .on_hover_text("Put the newest clip back on an empty clipboard when ClipVault starts");
+                                    ui.add_space(8.0);
+                                    ui.label("Never record copies from (one per line)")
+                                        .on_hover_text("Window class or process name, e.g. KeePassXC or firefox.");
+                                    ui.add(
+                                        egui::TextEdit::multiline(&mut ui_state.excluded_apps_text)
+                                            .desired_rows(3)
+                                            .desired_width(240.0)
+                                    );
+                                }
+                                ui.add_space(8.0);
+                                ui.horizontal(|ui| {
+                                    ui.label("Merge copies made within");
+                                    ui.add(egui::DragValue::new(&mut ui_state.coalesce_window_ms).range(0..=5000).suffix(" ms"));
+                                }).response.on_hover_text("Tools that write the clipboard several times in a row leave one clip, the last value. 0 records every write.");
+                                ui.checkbox(&mut ui_state.skip_cleared_copies, "Skip copies cleared right away")
+                                    .on_hover_text("Don't record a value that is set and cleared within that time, as password managers do");
+                                ui.add_space(8.0);
+                                egui::CollapsingHeader
+                                    ::new("Link cleaning")
+                                    .id_salt("url_cleaning")
+                                    .show(ui, |ui| url_cleaning_settings(ui, ui_state))
+                                    .header_response
+                                    .on_hover_text("Remove tracking parameters such as utm_source or fbclid from copied links.");
+                                egui::CollapsingHeader
+                                    ::new("Capture filters")
+                                    .id_salt("capture_filters")
+                                    .show(ui, |ui| {
+                                        capture_filter_settings(ui, ui_state);
+                                        if ui.button("Show dropped clips...").on_hover_text("Recent clips the filters dropped").clicked() {
+                                            response.show_capture_log = true;
+                                        }
+                                    })
+                                    .header_response
+                                    .on_hover_text("Applied to copied text before it is saved.");
                                 ui.add_space(32.0);
                                 // Reset Settings Button
                                 let reset_button = ui.add_sized(
@@ -191,4 +258,58 @@ impl SettingsView {
 pub struct SettingsResponse {
     pub delete_db: bool,
     pub reset_settings: bool,
+    pub show_capture_log: bool,
+    // Left through the back button, the changes are saved
+    pub closed: bool,
+}
+
+fn capture_filter_settings(ui: &mut egui::Ui, ui_state: &mut UiState) {
+    let filter = &mut ui_state.capture_filter;
+    ui.checkbox(&mut filter.normalize_line_endings, "Convert Windows line endings (CRLF)");
+    ui.checkbox(&mut filter.trim_whitespace, "Trim leading and trailing whitespace");
+    optional_limit(ui, &mut filter.max_trailing_newlines, "Keep at most", "trailing newlines", 1);
+    ui.checkbox(&mut filter.ignore_whitespace_only, "Ignore whitespace-only copies");
+    ui.checkbox(&mut filter.ignore_digits_only, "Ignore digit-only copies")
+        .on_hover_text("Such as one-time codes and PINs");
+    ui.horizontal(|ui| {
+        ui.label("Minimum length");
+        ui.add(egui::DragValue::new(&mut filter.min_length).range(0..=10_000).suffix(" chars"));
+    });
+    optional_limit(ui, &mut filter.max_length, "Maximum length", "chars", 100_000);
+    ui.label("Ignore copies matching (one regex per line)");
+    ui.add(
+        egui::TextEdit::multiline(&mut ui_state.ignore_patterns_text)
+            .desired_rows(3)
+            .desired_width(240.0)
+            .code_editor()
+    );
+}
+
+fn url_cleaning_settings(ui: &mut egui::Ui, ui_state: &mut UiState) {
+    let urls = &mut ui_state.url_cleaning;
+    ui.checkbox(&mut urls.enabled, "Strip tracking parameters from links");
+    ui.add_enabled_ui(urls.enabled, |ui| {
+        ui.checkbox(&mut urls.builtin_rules, "Use the built-in rules")
+            .on_hover_text("utm_*, fbclid, gclid, msclkid and similar everywhere, plus known parameters on YouTube, Amazon, X...");
+        ui.label("Extra rules, one domain per line")
+            .on_hover_text("e.g. example.com: ref, campaign_*   Use * as the domain for every site.");
+        ui.add(
+            egui::TextEdit::multiline(&mut ui_state.url_rules_text)
+                .desired_rows(3)
+                .desired_width(240.0)
+                .hint_text("example.com: ref, campaign_*")
+                .code_editor()
+        );
+    });
+}
+
+// A checkbox enabling a number, for settings that are off when None
+fn optional_limit(ui: &mut egui::Ui, value: &mut Option<usize>, label: &str, suffix: &str, default: usize) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        let mut limit = value.unwrap_or(default);
+        ui.checkbox(&mut enabled, label);
+        ui.add_enabled(enabled, egui::DragValue::new(&mut limit).range(0..=1_000_000).suffix(format!(" {}", suffix)));
+        *value = enabled.then_some(limit);
+    });
 }
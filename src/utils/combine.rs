@@ -0,0 +1,41 @@
+// Separator placed between clips when combining them into one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Separator {
+    #[default]
+    Newline,
+    Comma,
+    Space,
+    Custom,
+}
+
+impl Separator {
+    pub const ALL: [Separator; 4] = [Separator::Newline, Separator::Comma, Separator::Space, Separator::Custom];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Separator::Newline => "Newline",
+            Separator::Comma => "Comma",
+            Separator::Space => "Space",
+            Separator::Custom => "Custom",
+        }
+    }
+
+    // The text inserted between items; `custom` is only used for Separator::Custom
+    pub fn as_str<'a>(&self, custom: &'a str) -> &'a str {
+        match self {
+            Separator::Newline => "\n",
+            Separator::Comma => ",",
+            Separator::Space => " ",
+            Separator::Custom => custom,
+        }
+    }
+}
+
+// Join clip contents in order, putting `prefix` in front of every item
+pub fn combine_clips<S: AsRef<str>>(parts: &[S], separator: &str, prefix: &str) -> String {
+    parts
+        .iter()
+        .map(|part| format!("{}{}", prefix, part.as_ref()))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
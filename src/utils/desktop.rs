@@ -0,0 +1,33 @@
+// Small helpers for launching and placing ClipVault windows
+use std::path::PathBuf;
+
+// Another ClipVault executable installed next to the running one, e.g. "gui"
+pub fn sibling_exe(name: &str) -> PathBuf {
+    let mut path = std::env::current_exe().unwrap_or_default();
+    path.pop();
+    path.push(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+    path
+}
+
+// Top-left corner for a window of `size` opened at the pointer, kept on screen. Only X11 lets
+// clients ask where the pointer is, elsewhere windows open centred.
+#[cfg(target_os = "linux")]
+pub fn position_near_cursor(size: [f32; 2]) -> Option<[f32; 2]> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::ConnectionExt as _;
+
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return None;
+    }
+    let (conn, screen) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots.get(screen)?;
+    let pointer = conn.query_pointer(root.root).ok()?.reply().ok()?;
+    let x = (pointer.root_x as f32).min(root.width_in_pixels as f32 - size[0]).max(0.0);
+    let y = (pointer.root_y as f32).min(root.height_in_pixels as f32 - size[1]).max(0.0);
+    Some([x, y])
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn position_near_cursor(_size: [f32; 2]) -> Option<[f32; 2]> {
+    None
+}
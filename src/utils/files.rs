@@ -0,0 +1,88 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Open the folder containing `path` in the platform's file manager
+pub fn open_containing_folder(path: &Path) -> io::Result<()> {
+    let folder = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(path)
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut command = Command::new("explorer");
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(folder).spawn().map(|_| ())
+}
+
+// A copied path and what it pointed to when the clip was loaded, None once it is gone
+#[derive(Debug, Clone)]
+pub struct CopiedFile {
+    pub path: PathBuf,
+    pub metadata: Option<std::fs::Metadata>,
+}
+
+// Look up the paths of a files clip, once per load rather than on every frame
+pub fn stat_files(paths: Vec<PathBuf>) -> Vec<CopiedFile> {
+    paths
+        .into_iter()
+        .map(|path| CopiedFile { metadata: std::fs::metadata(&path).ok(), path })
+        .collect()
+}
+
+// Parse a text/uri-list as offered by file managers, keeping only local files
+pub fn parse_uri_list(list: &str) -> Vec<PathBuf> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("file://"))
+        // Skip the host part, "file://localhost/tmp" and "file:///tmp" are the same file
+        .filter_map(|rest| rest.find('/').map(|slash| &rest[slash..]))
+        .map(|path| PathBuf::from(percent_decode(path)))
+        .collect()
+}
+
+// Build a text/uri-list for the given files
+pub fn to_uri_list(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| format!("file://{}\r\n", percent_encode(&path.to_string_lossy())))
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn percent_encode(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -45,4 +45,19 @@ pub fn format_timestamp(timestamp: i64) -> String {
             )
         })
         .unwrap_or_else(|| "Invalid timestamp".to_string())
+}
+
+// Format a byte count for display, e.g. 1536 -> "1.5 KB"
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
 }
\ No newline at end of file
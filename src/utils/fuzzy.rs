@@ -0,0 +1,166 @@
+// Fuzzy matching for the quick-paste picker and the fuzzy search mode: every word of the query
+// has to appear in the text as a subsequence, so "kubctl gt pods" finds "kubectl get pods".
+// Tight, early and word-start matches rank higher.
+use nucleo_matcher::{ Config, Matcher, Utf32Str, pattern::{ AtomKind, CaseMatching, Normalization, Pattern } };
+use std::ops::Range;
+
+// Only the start of long clips is indexed, so one huge clip can't slow every keystroke
+const INDEXED_CHARS: usize = 2000;
+// Matches ranked by the full scoring, the rest keep the cheaper greedy order
+const RESCORED: usize = 1000;
+
+pub struct FuzzyMatch {
+    pub score: u32,
+    pub positions: Vec<usize>, // matched characters, sorted
+}
+
+pub struct FuzzyMatcher {
+    matcher: Matcher,
+    pattern: Pattern,
+    buf: Vec<char>,
+}
+
+impl FuzzyMatcher {
+    // Smart case: an uppercase letter in the query makes it case-sensitive
+    pub fn new(query: &str) -> Self {
+        Self {
+            matcher: Matcher::new(Config::DEFAULT),
+            pattern: Pattern::new(query, CaseMatching::Smart, Normalization::Smart, AtomKind::Fuzzy),
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pattern.atoms.is_empty()
+    }
+
+    pub fn score(&mut self, text: &str) -> Option<u32> {
+        self.pattern.score(Utf32Str::new(text, &mut self.buf), &mut self.matcher)
+    }
+
+    // Single greedy pass: the same clips match as with `score`, ranked more roughly, several
+    // times faster. The needle tells how the atom was built: uppercase only survives in
+    // case-sensitive atoms, accents only in ones that skip normalization.
+    pub fn quick_score(&mut self, text: &str) -> Option<u32> {
+        let haystack = Utf32Str::new(text, &mut self.buf);
+        let mut total = 0;
+        for atom in &self.pattern.atoms {
+            let needle = atom.needle_text();
+            self.matcher.config.ignore_case = !needle.chars().any(char::is_uppercase);
+            self.matcher.config.normalize = needle.chars().all(|c| c.is_ascii());
+            total += self.matcher.fuzzy_match_greedy(haystack, needle)? as u32;
+        }
+        Some(total)
+    }
+
+    pub fn find(&mut self, text: &str) -> Option<FuzzyMatch> {
+        let mut indices = Vec::new();
+        let score = self.pattern.indices(Utf32Str::new(text, &mut self.buf), &mut self.matcher, &mut indices)?;
+        indices.sort_unstable();
+        indices.dedup();
+        Some(FuzzyMatch { score, positions: indices.into_iter().map(|i| i as usize).collect() })
+    }
+}
+
+// Searchable text of every clip, kept in memory so fuzzy search doesn't reread the vault on
+// each keystroke. Typing more of the same query only rescans the previous matches.
+#[derive(Default)]
+pub struct FuzzyIndex {
+    entries: Vec<(i64, String)>, // (clip id, title, content and note), oldest first
+    last_query: String,
+    last_matches: Vec<(u32, usize)>, // (greedy score, entry) for `last_query`
+}
+
+impl FuzzyIndex {
+    // Highest clip id indexed so far, newer clips are added with `extend`
+    pub fn last_id(&self) -> i64 {
+        self.entries.last().map_or(0, |(id, _)| *id)
+    }
+
+    pub fn extend(&mut self, clips: impl IntoIterator<Item = (i64, String)>) {
+        let before = self.entries.len();
+        self.entries.extend(
+            clips.into_iter().map(|(id, text)| (id, text.chars().take(INDEXED_CHARS).collect()))
+        );
+        if self.entries.len() != before {
+            self.last_query.clear();
+            self.last_matches.clear();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // Ids of the clips matching `query`, best first and newest first among equals
+    pub fn search(&mut self, query: &str) -> Vec<i64> {
+        let mut matcher = FuzzyMatcher::new(query);
+        if matcher.is_empty() {
+            return Vec::new();
+        }
+        // Adding characters or words only narrows a fuzzy query
+        let narrowing = !self.last_query.is_empty() && query.starts_with(&self.last_query);
+        let matches: Vec<(u32, usize)> = if narrowing {
+            self.last_matches
+                .iter()
+                .filter_map(|&(_, entry)| Some((matcher.quick_score(&self.entries[entry].1)?, entry)))
+                .collect()
+        } else {
+            self.entries
+                .iter()
+                .enumerate()
+                .filter_map(|(entry, (_, text))| Some((matcher.quick_score(text)?, entry)))
+                .collect()
+        };
+
+        let mut ranked = matches.clone();
+        ranked.sort_unstable_by(|a, b| b.cmp(a));
+        let best = ranked.len().min(RESCORED);
+        for (score, entry) in &mut ranked[..best] {
+            *score = matcher.score(&self.entries[*entry].1).unwrap_or(*score);
+        }
+        ranked[..best].sort_unstable_by(|a, b| b.cmp(a));
+
+        self.last_query = query.to_string();
+        self.last_matches = matches;
+        ranked.into_iter().map(|(_, entry)| self.entries[entry].0).collect()
+    }
+}
+
+// What a clip is ranked by in the fuzzy search mode: its title, content and note. Also gives the
+// characters of the content in it, so matches can be highlighted in the content alone.
+pub fn clip_text(title: Option<&str>, content: &str, note: Option<&str>) -> (String, Range<usize>) {
+    let mut text = String::new();
+    if let Some(title) = title {
+        text.push_str(title);
+        text.push(' ');
+    }
+    let start = text.chars().count();
+    text.push_str(content);
+    let end = start + content.chars().count();
+    if let Some(note) = note {
+        text.push(' ');
+        text.push_str(note);
+    }
+    (text, start..end)
+}
+
+// Byte ranges of the matched characters in `text`, neighbours merged, for highlighting
+pub fn byte_ranges(text: &str, positions: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut wanted = positions.iter().peekable();
+    for (index, (start, c)) in text.char_indices().enumerate() {
+        if wanted.peek().is_none() {
+            break;
+        }
+        if wanted.next_if(|&&position| position == index).is_none() {
+            continue;
+        }
+        let end = start + c.len_utf8();
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+    ranges
+}
@@ -0,0 +1,13 @@
+use std::env;
+
+// Name of this machine, recorded with each clip
+pub fn local_hostname() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    if let Ok(name) = std::fs::read_to_string("/proc/sys/kernel/hostname") {
+        return Some(name.trim().to_string()).filter(|name| !name.is_empty());
+    }
+    ["HOSTNAME", "COMPUTERNAME"]
+        .into_iter()
+        .find_map(|var| env::var(var).ok())
+        .filter(|name| !name.is_empty())
+}
@@ -0,0 +1,91 @@
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ColorType, ImageEncoder, ImageError, ImageFormat, RgbaImage};
+
+// Longest edge of the thumbnails shown in clip cards
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+// An image clip ready to be stored: compressed PNG, thumbnail and pixel hash
+#[derive(Debug, Clone)]
+pub struct ClipImage {
+    pub width: u32,
+    pub height: u32,
+    pub png: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+    pub pixel_hash: String,
+}
+
+impl ClipImage {
+    // Build from raw RGBA8 pixels as read from the clipboard
+    pub fn from_rgba(width: u32, height: u32, rgba: Vec<u8>) -> Result<Self, ImageError> {
+        let pixel_hash = pixel_hash(width, height, &rgba);
+        let image = RgbaImage::from_raw(width, height, rgba).ok_or_else(|| {
+            ImageError::Parameter(image::error::ParameterError::from_kind(
+                image::error::ParameterErrorKind::DimensionMismatch,
+            ))
+        })?;
+
+        let (thumb_width, thumb_height) = thumbnail_dimensions(width, height);
+        let thumbnail = image::imageops::thumbnail(&image, thumb_width, thumb_height);
+
+        Ok(Self {
+            width,
+            height,
+            png: encode_png(&image)?,
+            thumbnail: encode_png(&thumbnail)?,
+            pixel_hash,
+        })
+    }
+
+    // Text stored as the clip's content, used for search and when content is hidden
+    pub fn label(&self) -> String {
+        format!("Image {}×{}", self.width, self.height)
+    }
+}
+
+// Stable FNV-1a hash of the dimensions and pixels, as hex
+pub fn pixel_hash(width: u32, height: u32, rgba: &[u8]) -> String {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let mut hash = OFFSET;
+    for byte in width.to_le_bytes().iter().chain(&height.to_le_bytes()).chain(rgba) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    format!("{:016x}", hash)
+}
+
+// Decode stored PNG bytes (full image or thumbnail) back to RGBA pixels
+pub fn decode_png(bytes: &[u8]) -> Result<RgbaImage, ImageError> {
+    Ok(image::load_from_memory_with_format(bytes, ImageFormat::Png)?.to_rgba8())
+}
+
+// Quick PNG for handing raw pixels to other programs, not for storage
+pub fn encode_rgba_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, ImageError> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png).write_image(rgba, width, height, ColorType::Rgba8)?;
+    Ok(png)
+}
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, ImageError> {
+    let mut png = Vec::new();
+    PngEncoder::new_with_quality(&mut png, CompressionType::Best, FilterType::Adaptive).write_image(
+        image.as_raw(),
+        image.width(),
+        image.height(),
+        ColorType::Rgba8,
+    )?;
+    Ok(png)
+}
+
+// Scale down to fit THUMBNAIL_SIZE, keeping the aspect ratio and never upscaling
+fn thumbnail_dimensions(width: u32, height: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= THUMBNAIL_SIZE {
+        return (width.max(1), height.max(1));
+    }
+    let scale = THUMBNAIL_SIZE as f32 / longest as f32;
+    (
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    )
+}
@@ -1 +1,10 @@
-pub mod formatting;
\ No newline at end of file
+pub mod combine;
+pub mod desktop;
+pub mod files;
+pub mod formatting;
+pub mod fuzzy;
+pub mod host;
+pub mod images;
+pub mod search;
+pub mod split;
+pub mod urls;
@@ -0,0 +1,331 @@
+use chrono::NaiveDate;
+use regex::{ Regex, RegexBuilder };
+use serde::{ Deserialize, Serialize };
+
+// How the free text of a query is matched against clips
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SearchMode {
+    #[default]
+    Substring,
+    Fuzzy, // ranked subsequence matching, see utils::fuzzy
+    Regex, // the whole query is a regular expression, source filters don't apply
+}
+
+// Flags for the regex search mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RegexFlags {
+    pub ignore_case: bool,
+    pub multiline: bool, // ^ and $ match at every line
+}
+
+impl RegexFlags {
+    // The pattern with the flags inlined, for SQLite's REGEXP
+    pub fn apply(self, pattern: &str) -> String {
+        match (self.ignore_case, self.multiline) {
+            (false, false) => pattern.to_string(),
+            (true, false) => format!("(?i){}", pattern),
+            (false, true) => format!("(?m){}", pattern),
+            (true, true) => format!("(?im){}", pattern),
+        }
+    }
+
+    // Checks the pattern before it goes to SQLite, and finds the ranges to highlight
+    pub fn compile(self, pattern: &str) -> Result<Regex, regex::Error> {
+        RegexBuilder::new(pattern).case_insensitive(self.ignore_case).multi_line(self.multiline).build()
+    }
+}
+
+// Qualifiers the search box understands, with a hint for autocomplete and error messages
+pub const QUALIFIERS: [(&str, &str); 9] = [
+    ("tag", "a tag name"),
+    ("pinned", "yes or no"),
+    ("kind", "text, image, files or url"),
+    ("after", "a day like 2025-01-01, copied on or after it"),
+    ("before", "a day like 2025-01-01, copied before it"),
+    ("len", "a length like >500, <=20 or 42"),
+    ("app", "an application name"),
+    ("window", "a window title"),
+    ("host", "a host name"),
+];
+
+const KINDS: [&str; 4] = ["text", "image", "files", "url"];
+const MAX_COMPLETIONS: usize = 8;
+
+// A search box query: words and "exact phrases" matched in content, title and note, and
+// qualifiers, e.g. `invoice tag:work -tag:old after:2025-01-01 OR "exact phrase" app:firefox`.
+// Terms are ANDed, OR separates alternatives and a leading - negates a term.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub groups: Vec<Vec<Term>>, // alternatives, each holding when all of its terms do
+    pub errors: Vec<String>,    // qualifier values that made no sense, searched as plain text
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub negated: bool,
+    pub filter: Filter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    Text(String), // a word or phrase in content, title or note
+    Tag(String),
+    Pinned(bool),
+    Kind(KindFilter),
+    After(NaiveDate),  // copied on or after the day (local time)
+    Before(NaiveDate), // copied before the day
+    Length(Comparison, i64),
+    App(String),
+    Window(String),
+    Host(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KindFilter {
+    Text,
+    Image,
+    Files,
+    Url, // text clips holding a single link
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    pub fn sql(self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "=",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Greater => ">",
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = SearchQuery::default();
+        let mut group = Vec::new();
+        for token in tokenize(query) {
+            if token.text == "OR" && !token.phrase && !token.negated {
+                if !group.is_empty() {
+                    parsed.groups.push(std::mem::take(&mut group));
+                }
+                continue;
+            }
+            if let Some(filter) = parsed.filter(&token) {
+                group.push(Term { negated: token.negated, filter });
+            }
+        }
+        if !group.is_empty() {
+            parsed.groups.push(group);
+        }
+        parsed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    // Fuzzy mode ranks the plain words and phrases and leaves everything else to SQL. Each
+    // alternative gives its words and a query of its remaining terms, empty when there are none.
+    pub fn split_text(self) -> Vec<(String, SearchQuery)> {
+        self.groups
+            .into_iter()
+            .map(|group| {
+                let mut words = Vec::new();
+                let mut filters = Vec::new();
+                for term in group {
+                    match term {
+                        Term { negated: false, filter: Filter::Text(text) } => words.push(text),
+                        term => filters.push(term),
+                    }
+                }
+                let groups = if filters.is_empty() { Vec::new() } else { vec![filters] };
+                (words.join(" "), SearchQuery { groups, errors: Vec::new() })
+            })
+            .collect()
+    }
+
+    // None drops the token: a qualifier still waiting for its value
+    fn filter(&mut self, token: &Token) -> Option<Filter> {
+        let text = || Filter::Text(token.text.clone());
+        let Some((name, value)) = token.text.split_once(':').filter(|_| !token.phrase) else {
+            return Some(text());
+        };
+        let name = name.to_ascii_lowercase();
+        let Some((name, hint)) = QUALIFIERS.iter().find(|(qualifier, _)| *qualifier == name) else {
+            return Some(text()); // e.g. https://...
+        };
+        if value.is_empty() {
+            return None;
+        }
+        let filter = match *name {
+            "tag" => Some(Filter::Tag(value.to_string())),
+            "pinned" => parse_bool(value).map(Filter::Pinned),
+            "kind" => parse_kind(value).map(Filter::Kind),
+            "after" => parse_date(value).map(Filter::After),
+            "before" => parse_date(value).map(Filter::Before),
+            "len" => parse_length(value).map(|(comparison, length)| Filter::Length(comparison, length)),
+            "app" => Some(Filter::App(value.to_string())),
+            "window" => Some(Filter::Window(value.to_string())),
+            _ => Some(Filter::Host(value.to_string())),
+        };
+        if filter.is_none() {
+            self.errors.push(format!("{}: expects {}, not \"{}\"", name, hint, value));
+        }
+        Some(filter.unwrap_or_else(text))
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "yes" | "true" | "1" => Some(true),
+        "no" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_kind(value: &str) -> Option<KindFilter> {
+    match value.to_ascii_lowercase().as_str() {
+        "text" => Some(KindFilter::Text),
+        "image" => Some(KindFilter::Image),
+        "files" | "file" => Some(KindFilter::Files),
+        "url" | "link" => Some(KindFilter::Url),
+        _ => None,
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+fn parse_length(value: &str) -> Option<(Comparison, i64)> {
+    let (comparison, number) = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ]
+        .into_iter()
+        .find_map(|(prefix, comparison)| value.strip_prefix(prefix).map(|number| (comparison, number)))
+        .unwrap_or((Comparison::Equal, value));
+    number.parse::<u32>().ok().map(|length| (comparison, length as i64))
+}
+
+// A suggestion for the token being typed, `query` being the whole search box once accepted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub hint: &'static str,
+    pub query: String,
+}
+
+// Autocomplete for the end of `query`: qualifier names, then tag names, kinds or yes/no after
+// `tag:`, `kind:` and `pinned:`
+pub fn completions(query: &str, tag_names: &[String]) -> Vec<Completion> {
+    // Nothing to complete after a space or inside an open quote
+    if query.ends_with(char::is_whitespace) || query.matches('"').count() % 2 == 1 {
+        return Vec::new();
+    }
+    // Whitespace such as a no-break space is more than one byte long
+    let start = query
+        .char_indices()
+        .rfind(|(_, c)| c.is_whitespace())
+        .map_or(0, |(index, c)| index + c.len_utf8());
+    let (before, token) = query.split_at(start);
+    let (negation, body) = match token.strip_prefix('-') {
+        Some(body) => ("-", body),
+        None => ("", token),
+    };
+
+    let Some((name, value)) = body.split_once(':') else {
+        if body.is_empty() {
+            return Vec::new();
+        }
+        let body = body.to_ascii_lowercase();
+        return QUALIFIERS
+            .iter()
+            .filter(|(qualifier, _)| qualifier.starts_with(&body))
+            .map(|&(qualifier, hint)| Completion {
+                label: format!("{}:", qualifier),
+                hint,
+                query: format!("{}{}{}:", before, negation, qualifier),
+            })
+            .collect();
+    };
+
+    let name = name.to_ascii_lowercase();
+    let value = value.to_lowercase();
+    let values: Vec<&str> = match name.as_str() {
+        "tag" => tag_names.iter().map(String::as_str).collect(),
+        "kind" => KINDS.to_vec(),
+        "pinned" => vec!["yes", "no"],
+        _ => return Vec::new(),
+    };
+    values
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().starts_with(&value) && candidate.to_lowercase() != value)
+        .take(MAX_COMPLETIONS)
+        .map(|candidate| {
+            let candidate = if candidate.contains(char::is_whitespace) {
+                format!("\"{}\"", candidate)
+            } else {
+                candidate.to_string()
+            };
+            Completion {
+                label: format!("{}:{}", name, candidate),
+                hint: "",
+                query: format!("{}{}{}:{} ", before, negation, name, candidate),
+            }
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Token {
+    text: String,
+    negated: bool, // written with a leading -
+    phrase: bool,  // started with a quote, so never a qualifier or OR
+}
+
+// Split on whitespace, keeping double-quoted parts (also after `app:`) together
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut token = Token::default();
+    let mut fresh = true; // nothing but a - read for this token yet
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => {
+                token.phrase |= fresh;
+                fresh = false;
+                quoted = !quoted;
+            }
+            '-' if fresh && !token.negated => token.negated = true,
+            c if c.is_whitespace() && !quoted => {
+                if !token.text.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+                token = Token::default();
+                fresh = true;
+            }
+            c => {
+                token.text.push(c);
+                fresh = false;
+            }
+        }
+    }
+    if !token.text.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
@@ -0,0 +1,43 @@
+use regex::Regex;
+
+// How a clip is broken up into separate clips
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplitMode {
+    #[default]
+    Lines,
+    Delimiter,
+    Regex,
+}
+
+impl SplitMode {
+    pub const ALL: [SplitMode; 3] = [SplitMode::Lines, SplitMode::Delimiter, SplitMode::Regex];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SplitMode::Lines => "One per line",
+            SplitMode::Delimiter => "By delimiter",
+            SplitMode::Regex => "Regex matches",
+        }
+    }
+}
+
+// Split `content` into trimmed, non-empty pieces. `pattern` is the delimiter or
+// regular expression; in regex mode every match becomes one piece.
+pub fn split_clip(content: &str, mode: SplitMode, pattern: &str) -> Result<Vec<String>, regex::Error> {
+    let pieces: Vec<&str> = match mode {
+        SplitMode::Lines => content.lines().collect(),
+        SplitMode::Delimiter if pattern.is_empty() => vec![content],
+        SplitMode::Delimiter => content.split(pattern).collect(),
+        SplitMode::Regex => Regex::new(pattern)?
+            .find_iter(content)
+            .map(|m| m.as_str())
+            .collect(),
+    };
+
+    Ok(pieces
+        .into_iter()
+        .map(str::trim)
+        .filter(|piece| !piece.is_empty())
+        .map(str::to_string)
+        .collect())
+}
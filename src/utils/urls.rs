@@ -0,0 +1,143 @@
+// Removes tracking parameters (utm_*, fbclid, gclid...) from copied links. The query
+// string is edited as text so everything else in the URL stays exactly as copied.
+use serde::{ Deserialize, Serialize };
+
+// Parameters removed everywhere, a trailing `*` matches any suffix
+const GLOBAL_PARAMS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "dclid", "gbraid", "wbraid", "gclsrc", "msclkid", "yclid", "twclid",
+    "ttclid", "li_fat_id", "igshid", "mc_cid", "mc_eid", "_hsenc", "_hsmi", "mkt_tok", "oly_anon_id",
+    "oly_enc_id", "vero_id", "vero_conv", "__s", "_openstat",
+];
+
+// Parameters that only track on particular sites
+const SITE_PARAMS: &[(&str, &[&str])] = &[
+    ("youtube.com", &["si", "feature", "pp"]),
+    ("youtu.be", &["si", "feature"]),
+    ("open.spotify.com", &["si", "context"]),
+    ("instagram.com", &["igsh"]),
+    ("twitter.com", &["s", "t", "ref_src", "ref_url"]),
+    ("x.com", &["s", "t"]),
+    ("amazon.*", &["ref", "ref_", "pd_rd_*", "pf_rd_*", "_encoding", "content-id", "psc"]),
+    ("reddit.com", &["share_id", "rdt"]),
+    ("linkedin.com", &["trk", "trackingId", "lipi"]),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct UrlCleaning {
+    pub enabled: bool,
+    pub builtin_rules: bool,
+    pub rules: Vec<UrlRule>,
+}
+
+impl Default for UrlCleaning {
+    fn default() -> Self {
+        Self { enabled: true, builtin_rules: true, rules: Vec::new() }
+    }
+}
+
+// Parameters to remove on one domain and its subdomains, on every site for "*"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UrlRule {
+    pub domain: String,
+    pub params: Vec<String>,
+}
+
+impl UrlRule {
+    // "example.com: ref, utm_*"
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let (domain, params) = line.split_once(':')?;
+        let domain = domain.trim().trim_start_matches("*.").to_lowercase();
+        let params: Vec<String> = params
+            .split([',', ' '])
+            .map(str::trim)
+            .filter(|param| !param.is_empty())
+            .map(String::from)
+            .collect();
+        (!domain.is_empty() && !params.is_empty()).then_some(Self { domain, params })
+    }
+
+    pub fn to_line(&self) -> String {
+        format!("{}: {}", self.domain, self.params.join(", "))
+    }
+}
+
+impl UrlCleaning {
+    // The cleaned text when it is a single http(s) link that lost parameters, else None.
+    // Whitespace around the link is kept.
+    pub fn clean(&self, text: &str) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let url = text.trim();
+        let start = text.len() - text.trim_start().len();
+        if url.chars().any(char::is_whitespace) {
+            return None;
+        }
+        let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"))?;
+        let host = host(rest).to_lowercase();
+
+        let (base, fragment) = match url.find('#') {
+            Some(hash) => url.split_at(hash),
+            None => (url, ""),
+        };
+        let (path, query) = base.split_once('?')?;
+        let kept: Vec<&str> = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| {
+                let key = pair.split_once('=').map_or(*pair, |(key, _)| key);
+                !self.is_tracking(&host, key)
+            })
+            .collect();
+        if kept.len() == query.split('&').filter(|pair| !pair.is_empty()).count() {
+            return None;
+        }
+
+        let mut cleaned = path.to_string();
+        if !kept.is_empty() {
+            cleaned.push('?');
+            cleaned.push_str(&kept.join("&"));
+        }
+        cleaned.push_str(fragment);
+        Some(format!("{}{}{}", &text[..start], cleaned, &text[start + url.len()..]))
+    }
+
+    fn is_tracking(&self, host: &str, key: &str) -> bool {
+        let builtin = self.builtin_rules.then_some(
+            GLOBAL_PARAMS.iter().copied().chain(
+                SITE_PARAMS
+                    .iter()
+                    .filter(|(domain, _)| on_domain(host, domain))
+                    .flat_map(|(_, params)| params.iter().copied()),
+            ),
+        );
+        let user = self.rules
+            .iter()
+            .filter(|rule| rule.domain == "*" || on_domain(host, &rule.domain))
+            .flat_map(|rule| rule.params.iter().map(String::as_str));
+        builtin.into_iter().flatten().chain(user).any(|param| param_matches(param, key))
+    }
+}
+
+// Host of a URL without the scheme, minus any user info and port
+fn host(rest: &str) -> &str {
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    host.split(':').next().unwrap_or_default()
+}
+
+// "amazon.*" matches amazon.de, www.amazon.co.uk...
+fn on_domain(host: &str, domain: &str) -> bool {
+    match domain.strip_suffix(".*") {
+        Some(name) => host.split('.').rev().skip(1).any(|label| label == name),
+        None => host == domain || host.ends_with(&format!(".{}", domain)),
+    }
+}
+
+fn param_matches(pattern: &str, key: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => key == pattern,
+    }
+}
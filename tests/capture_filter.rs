@@ -0,0 +1,111 @@
+// The capture filter pipeline: the rewriting stages, the checks that drop a clip and
+// the stage each outcome is blamed on.
+use clipvault::clipboard::filter::{ CaptureFilter, FilterOutcome, FilterSettings, FilterStage };
+use clipvault::utils::urls::UrlCleaning;
+
+fn filter(settings: FilterSettings) -> CaptureFilter {
+    CaptureFilter::new(settings, UrlCleaning { enabled: false, ..Default::default() })
+}
+
+fn accepted(text: &str, changed_by: &[FilterStage]) -> FilterOutcome {
+    FilterOutcome::Accepted { text: text.to_string(), changed_by: changed_by.to_vec() }
+}
+
+fn dropped_at(outcome: FilterOutcome) -> FilterStage {
+    match outcome {
+        FilterOutcome::Dropped { stage, .. } => stage,
+        other => panic!("expected the clip to be dropped, got {:?}", other),
+    }
+}
+
+#[test]
+fn defaults_keep_text_as_copied() {
+    let filter = filter(FilterSettings::default());
+    assert_eq!(filter.apply("  hello\r\n\n\n"), accepted("  hello\r\n\n\n", &[]));
+    assert_eq!(filter.apply("x"), accepted("x", &[]));
+    assert_eq!(dropped_at(filter.apply(" \t\n")), FilterStage::WhitespaceOnly);
+}
+
+#[test]
+fn line_endings_become_lf() {
+    let filter = filter(FilterSettings { normalize_line_endings: true, ..Default::default() });
+    assert_eq!(filter.apply("a\r\nb\rc\nd"), accepted("a\nb\nc\nd", &[FilterStage::LineEndings]));
+    assert_eq!(filter.apply("a\nb"), accepted("a\nb", &[]));
+}
+
+#[test]
+fn trailing_newlines_are_capped() {
+    let capped = |max, text: &str| {
+        match filter(FilterSettings { max_trailing_newlines: Some(max), ..Default::default() }).apply(text) {
+            FilterOutcome::Accepted { text, .. } => text,
+            other => panic!("expected the clip to be accepted, got {:?}", other),
+        }
+    };
+    assert_eq!(capped(1, "line\n\n\n"), "line\n");
+    assert_eq!(capped(0, "line\n\n"), "line");
+    assert_eq!(capped(2, "line\n"), "line\n");
+    // A CRLF counts as one line break and stays whole
+    assert_eq!(capped(1, "line\r\n\r\n"), "line\r\n");
+    // Only the end of the text is touched
+    assert_eq!(capped(0, "a\n\n\nb\n"), "a\n\n\nb");
+}
+
+#[test]
+fn rewrites_run_before_the_checks() {
+    let settings = FilterSettings {
+        normalize_line_endings: true,
+        trim_whitespace: true,
+        max_trailing_newlines: Some(1),
+        ..Default::default()
+    };
+    let rewriting = filter(settings);
+    assert_eq!(rewriting.apply("  a\r\nb  "), accepted("a\nb", &[FilterStage::LineEndings, FilterStage::Trim]));
+
+    // Trimmed down to nothing, so it has no length left
+    let trimming = filter(FilterSettings { trim_whitespace: true, ignore_whitespace_only: false, ..Default::default() });
+    assert_eq!(dropped_at(trimming.apply("   ")), FilterStage::MinLength);
+}
+
+#[test]
+fn length_bounds_count_characters() {
+    let filter = filter(FilterSettings { min_length: 3, max_length: Some(5), ..Default::default() });
+    assert_eq!(dropped_at(filter.apply("ab")), FilterStage::MinLength);
+    assert_eq!(filter.apply("abc"), accepted("abc", &[]));
+    assert_eq!(filter.apply("abcde"), accepted("abcde", &[]));
+    assert_eq!(dropped_at(filter.apply("abcdef")), FilterStage::MaxLength);
+    // Five characters, but more than five bytes
+    assert_eq!(filter.apply("ééééé"), accepted("ééééé", &[]));
+    assert_eq!(dropped_at(filter.apply("🦀🦀")), FilterStage::MinLength);
+}
+
+#[test]
+fn digits_and_patterns_drop_clips() {
+    let settings = FilterSettings {
+        ignore_digits_only: true,
+        ignore_patterns: vec!["^sk-[A-Za-z0-9]+$".to_string(), "(".to_string()],
+        ..Default::default()
+    };
+    // The invalid pattern is skipped, the valid one still applies
+    let filter = filter(settings);
+    assert_eq!(dropped_at(filter.apply(" 123456 ")), FilterStage::DigitsOnly);
+    assert_eq!(filter.apply("123 456"), accepted("123 456", &[]));
+    assert_eq!(dropped_at(filter.apply("sk-abc123")), FilterStage::Pattern);
+    assert_eq!(filter.apply("my sk-abc123"), accepted("my sk-abc123", &[]));
+}
+
+#[test]
+fn log_entries_name_the_stage() {
+    let filter = filter(FilterSettings { min_length: 4, ..Default::default() });
+    let entry = filter.apply("abc").log_entry("abc", 100, None);
+    assert!(!entry.accepted);
+    assert_eq!(entry.stage, "min-length");
+    assert_eq!(entry.detail, "3 characters, minimum is 4");
+
+    let links = CaptureFilter::new(FilterSettings::default(), UrlCleaning::default());
+    let outcome = links.apply("https://example.com/?utm_source=x");
+    assert!(outcome.changed(FilterStage::CleanUrl));
+    let entry = outcome.log_entry("https://example.com/?utm_source=x", 100, Some(7));
+    assert!(entry.accepted);
+    assert_eq!(entry.detail, "rewritten by clean-url");
+    assert_eq!(entry.clip_id, Some(7));
+}
@@ -0,0 +1,119 @@
+// Exercises org.clipvault.Vault against a private dbus-daemon and an in-memory vault, so it
+// never touches the user's session bus or history. Needs dbus-daemon; set
+// CLIPVAULT_SKIP_DBUS_TESTS=1 to skip it on machines without one.
+#![cfg(target_os = "linux")]
+
+use std::{
+    fs,
+    io::{ BufRead, BufReader },
+    path::PathBuf,
+    process::{ Child, Command, Stdio },
+    sync::{ Arc, Mutex },
+};
+use clipvault::{ db, dbus };
+use clipvault::models::{ DaemonEvent, PauseState };
+use zbus::blocking::{ Proxy, connection::Builder };
+
+type ClipStruct = (i64, String, String, i64, bool);
+
+struct PrivateBus {
+    daemon: Child,
+    address: String,
+    dir: PathBuf,
+}
+
+impl PrivateBus {
+    fn start() -> Self {
+        let dir = std::env::temp_dir().join(format!("clipvault-dbus-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .arg(format!("--address=unix:path={}", dir.join("bus").display()))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("dbus-daemon must be installed to run this test (CLIPVAULT_SKIP_DBUS_TESTS=1 skips it)");
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+        Self { daemon, address: address.trim().to_string(), dir }
+    }
+
+    fn connect(&self) -> Builder<'static> {
+        Builder::address(self.address.as_str()).expect("valid bus address")
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn vault_interface_on_private_bus() {
+    if std::env::var_os("CLIPVAULT_SKIP_DBUS_TESTS").is_some() {
+        return;
+    }
+    let bus = PrivateBus::start();
+
+    let conn = db::init_db_at(":memory:").unwrap();
+    let pinned = db::add_text_clip(&conn, "pinned clip", None, None, &[], true).unwrap();
+    db::add_text_clip(&conn, "invoice 42", None, None, &[], false).unwrap();
+    let vault = Arc::new(Mutex::new(conn));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let notified = events.clone();
+    let notify = Arc::new(move |event| notified.lock().unwrap().push(event));
+    let service = dbus::serve_on(bus.connect(), vault.clone(), notify).unwrap();
+
+    let client = bus.connect().build().unwrap();
+    let proxy = Proxy::new(&client, dbus::BUS_NAME, dbus::OBJECT_PATH, "org.clipvault.Vault").unwrap();
+
+    let history: Vec<ClipStruct> = proxy.call("History", &(10u32,)).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].0, pinned);
+    assert!(history[0].4);
+
+    let found: Vec<ClipStruct> = proxy.call("Search", &("invoice", 10u32)).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].2, "invoice 42");
+
+    proxy.call::<_, _, ()>("Pause", &(15u32,)).unwrap();
+    let (recording, label): (bool, String) = proxy.call("Status", &()).unwrap();
+    assert!(!recording);
+    assert!(label.starts_with("Incognito"), "{label}");
+    // Longer than a year is refused rather than stored
+    match proxy.call::<_, _, ()>("Pause", &(u32::MAX,)) {
+        Err(zbus::Error::MethodError(name, _, _)) => {
+            assert_eq!(name.as_str(), "org.clipvault.Vault.Error.InvalidArgs");
+        }
+        other => panic!("expected InvalidArgs, got {other:?}"),
+    }
+    proxy.call::<_, _, ()>("Resume", &()).unwrap();
+    let (recording, _): (bool, String) = proxy.call("Status", &()).unwrap();
+    assert!(recording);
+    // The tray hears about both without polling
+    let events = events.lock().unwrap().clone();
+    assert!(
+        matches!(
+            events[..],
+            [DaemonEvent::PauseChanged(PauseState::Incognito { .. }), DaemonEvent::PauseChanged(PauseState::Recording)]
+        ),
+        "{events:?}"
+    );
+
+    match proxy.call::<_, _, ()>("Copy", &(9999i64,)) {
+        Err(zbus::Error::MethodError(name, _, _)) => {
+            assert_eq!(name.as_str(), "org.clipvault.Vault.Error.NotFound");
+        }
+        other => panic!("expected NotFound, got {other:?}"),
+    }
+
+    let mut signals = proxy.receive_signal("ClipAdded").unwrap();
+    let id = db::add_text_clip(&vault.lock().unwrap(), "new clip", None, None, &[], false).unwrap();
+    service.clip_added(&vault.lock().unwrap(), id);
+    let message = signals.next().expect("ClipAdded signal");
+    let (signal_id, kind, content, _timestamp): (i64, String, String, i64) = message.body().deserialize().unwrap();
+    assert_eq!((signal_id, kind.as_str(), content.as_str()), (id, "text", "new clip"));
+}
@@ -0,0 +1,34 @@
+// Exporting a vault and importing it into another one.
+use clipvault::db;
+use rusqlite::{ Connection, params };
+
+// Pinned clips in the given order, each copied at the given time
+fn vault(pins: &[(&str, i64)]) -> Connection {
+    db::set_quiet(true);
+    let conn = db::init_db_at(":memory:").unwrap();
+    for (index, (content, timestamp)) in pins.iter().enumerate() {
+        let id = db::add_text_clip(&conn, content, None, None, &[], true).unwrap();
+        conn.execute("UPDATE clips SET timestamp = ?1 WHERE id = ?2", params![timestamp, id]).unwrap();
+        db::move_pinned_clip(&conn, id, index).unwrap();
+    }
+    conn
+}
+
+fn contents(conn: &Connection) -> Vec<String> {
+    db::load_recent_clips(conn, 100).unwrap().into_iter().map(|clip| clip.1).collect()
+}
+
+#[test]
+fn imported_pins_follow_the_existing_ones() {
+    let conn = vault(&[("mine 1", 1000), ("mine 2", 1001)]);
+    // Copied later, so the order can't come from the timestamps
+    let other = vault(&[("theirs 1", 2000), ("theirs 2", 2001)]);
+    assert_eq!(contents(&other), ["theirs 1", "theirs 2"]);
+
+    db::import_vault(&conn, &db::export_vault(&other).unwrap()).unwrap();
+    assert_eq!(contents(&conn), ["mine 1", "mine 2", "theirs 1", "theirs 2"]);
+
+    // Importing the same clips again changes nothing
+    db::import_vault(&conn, &db::export_vault(&other).unwrap()).unwrap();
+    assert_eq!(contents(&conn), ["mine 1", "mine 2", "theirs 1", "theirs 2"]);
+}
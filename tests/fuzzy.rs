@@ -0,0 +1,124 @@
+// Fuzzy matching and the in-memory index behind the fuzzy search mode and the picker.
+use std::time::{ Duration, Instant };
+use clipvault::utils::fuzzy::{ FuzzyIndex, FuzzyMatcher, byte_ranges, clip_text };
+
+fn index(texts: &[&str]) -> FuzzyIndex {
+    let mut index = FuzzyIndex::default();
+    index.extend(texts.iter().enumerate().map(|(i, text)| (i as i64 + 1, text.to_string())));
+    index
+}
+
+#[test]
+fn words_match_as_subsequences() {
+    let mut matcher = FuzzyMatcher::new("kubctl gt pods");
+    assert!(matcher.score("kubectl get pods -A").is_some());
+    assert!(matcher.score("kubectl get nodes").is_none());
+    // Every word has to match, in any order
+    assert!(FuzzyMatcher::new("pods kubctl").score("kubectl get pods").is_some());
+    assert!(FuzzyMatcher::new("   ").is_empty());
+}
+
+#[test]
+fn smart_case() {
+    assert!(FuzzyMatcher::new("readme").score("README.md").is_some());
+    assert!(FuzzyMatcher::new("README").score("readme.md").is_none());
+    assert!(FuzzyMatcher::new("Readme").score("Readme.md").is_some());
+}
+
+#[test]
+fn tight_and_word_start_matches_rank_first() {
+    let mut index = index(&["git checkout main", "go to the chat", "git commit", "gc"]);
+    assert_eq!(index.search("gc"), [4, 3, 1, 2]);
+    assert_eq!(index.search("checkout"), [1]);
+}
+
+#[test]
+fn equal_scores_list_newest_first() {
+    let mut index = index(&["deploy", "deploy", "deploy"]);
+    assert_eq!(index.search("deploy"), [3, 2, 1]);
+}
+
+#[test]
+fn narrowing_finds_what_a_full_search_finds() {
+    let texts: Vec<String> = (0..2000).map(|i| format!("item {} {}", i, ["alpha", "beta", "gamma", "delta"][i % 4])).collect();
+    let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let mut typed = index(&texts);
+    let mut query = String::new();
+    for c in "item 1 alp".chars() {
+        query.push(c);
+        let narrowed = typed.search(&query);
+        assert_eq!(narrowed, index(&texts).search(&query), "after typing {:?}", query);
+    }
+    // Deleting characters widens the search again
+    assert_eq!(typed.search("item 1"), index(&texts).search("item 1"));
+
+    // New clips reset what the last query found
+    typed.extend([(5000, "item 1 alpha, added later".to_string())]);
+    assert!(typed.search("item 1 alp").contains(&5000));
+}
+
+#[test]
+fn byte_ranges_follow_multi_byte_characters() {
+    // Character positions, not bytes: "é" and "🦀" are several bytes each
+    let text = "café 🦀 crab";
+    let highlighted = |positions: &[usize]| -> Vec<&str> {
+        byte_ranges(text, positions).into_iter().map(|range| &text[range]).collect()
+    };
+    assert_eq!(highlighted(&[0, 1]), ["ca"]);
+    assert_eq!(highlighted(&[3, 4, 5]), ["é 🦀"]);
+    assert_eq!(byte_ranges(text, &[5, 7, 9]), [6..10, 11..12, 13..14]);
+    assert!(byte_ranges(text, &[]).is_empty());
+    assert!(byte_ranges(text, &[40]).is_empty());
+
+    let mut matcher = FuzzyMatcher::new("🦀cr");
+    let found = matcher.find(text).unwrap();
+    let ranges = byte_ranges(text, &found.positions);
+    let matched: String = ranges.iter().map(|range| &text[range.clone()]).collect();
+    assert_eq!(matched, "🦀cr");
+}
+
+#[test]
+fn clip_text_locates_the_content() {
+    let (text, content) = clip_text(Some("Déjà vu"), "kubectl get pods", Some("cluster"));
+    assert_eq!(text, "Déjà vu kubectl get pods cluster");
+    assert_eq!(content, 8..24);
+    assert_eq!(clip_text(None, "pods", None), ("pods".to_string(), 0..4));
+
+    // A match spanning title and content only highlights its content part
+    let mut matcher = FuzzyMatcher::new("vu kub");
+    let found = matcher.find(&text).unwrap();
+    let positions: Vec<usize> = found.positions
+        .iter()
+        .filter(|position| content.contains(position))
+        .map(|position| position - content.start)
+        .collect();
+    let ranges = byte_ranges("kubectl get pods", &positions);
+    assert_eq!(ranges.iter().map(|range| &"kubectl get pods"[range.clone()]).collect::<String>(), "kub");
+}
+
+#[test]
+fn long_clips_are_matched_by_their_start() {
+    let long = format!("{}needle", "x".repeat(5000));
+    let mut index = index(&["needle", &long]);
+    assert_eq!(index.search("needle"), [1]);
+}
+
+// Keystrokes over a large vault stay interactive, a debug build included
+#[test]
+fn searches_100k_clips_quickly() {
+    let words = ["kubectl", "get", "pods", "invoice", "deploy", "docker", "cargo", "build", "release", "https://example.com"];
+    let texts: Vec<String> = (0..100_000)
+        .map(|i| format!("{} {} {} #{}", words[i % 10], words[i / 10 % 10], words[i / 100 % 10], i))
+        .collect();
+    let mut index = FuzzyIndex::default();
+    index.extend(texts.into_iter().enumerate().map(|(i, text)| (i as i64, text)));
+
+    let started = Instant::now();
+    let mut found = 0;
+    for query in ["k", "ku", "kub", "kubc", "kubct", "kubctl", "kubctl g", "kubctl gp"] {
+        found = index.search(query).len();
+    }
+    let elapsed = started.elapsed();
+    assert!(found > 0);
+    assert!(elapsed < Duration::from_secs(5), "eight keystrokes took {:?}", elapsed);
+}
@@ -0,0 +1,269 @@
+// Drives the clipboard monitor through a MockBackend: what gets recorded, what is skipped and
+// what the monitor itself puts on the clipboard. A sentinel copy after every skipped value shows
+// nothing was recorded before it.
+use std::{
+    path::PathBuf,
+    sync::mpsc::{ self, Receiver, Sender },
+    thread::{ self, JoinHandle },
+    time::{ Duration, Instant },
+};
+use clipvault::clipboard::{ CapturedClip, ClipboardBackend, ImageData, MonitorCommand, MonitorOptions, SelectionSync, monitor_with_backend };
+use clipvault::clipboard::backend::{ MockBackend, MockContent };
+use clipvault::models::{ ClipFormat, ClipOrigin, Selection };
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Running {
+    clipboard: MockBackend,
+    commands: Sender<MonitorCommand>,
+    clips: Receiver<(CapturedClip, ClipOrigin)>,
+    handle: JoinHandle<()>,
+}
+
+fn start(clipboard: &MockBackend, options: MonitorOptions) -> Running {
+    let (clip_tx, clips) = mpsc::channel();
+    let (commands, handle) = monitor_with_backend(Box::new(clipboard.clone()), options, move |clip, origin, _| {
+        let _ = clip_tx.send((clip, origin));
+    })
+    .unwrap();
+    Running { clipboard: clipboard.clone(), commands, clips, handle }
+}
+
+impl Running {
+    fn next(&self) -> CapturedClip {
+        self.clips.recv_timeout(TIMEOUT).expect("a clip to be recorded").0
+    }
+
+    fn next_text(&self) -> String {
+        match self.next() {
+            CapturedClip::Text { text, .. } => text,
+            other => panic!("expected a text clip, got {:?}", other),
+        }
+    }
+
+    // Nothing but the sentinel was recorded since the last clip
+    fn expect_only_sentinel(&self) {
+        self.clipboard.copy_text("sentinel");
+        assert_eq!(self.next_text(), "sentinel");
+    }
+
+    fn stop(self) {
+        self.commands.send(MonitorCommand::Stop).unwrap();
+        self.handle.join().unwrap();
+    }
+}
+
+fn app(name: &str) -> Option<ClipOrigin> {
+    Some(ClipOrigin { app: Some(name.to_string()), ..Default::default() })
+}
+
+fn clipboard_text(clipboard: &MockBackend) -> Option<String> {
+    match clipboard.contents() {
+        Some(MockContent::Text { text, .. }) => Some(text),
+        _ => None,
+    }
+}
+
+// Wait for the monitor to change the clipboard on its own
+fn wait_for(mut done: impl FnMut() -> bool) {
+    let deadline = Instant::now() + TIMEOUT;
+    while !done() {
+        assert!(Instant::now() < deadline, "timed out waiting for the monitor");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn records_text_with_its_origin() {
+    let clipboard = MockBackend::new();
+    let monitor = start(&clipboard, MonitorOptions::default());
+
+    clipboard.set_origin(app("firefox"));
+    clipboard.copy_text("hello");
+    let (clip, origin) = monitor.clips.recv_timeout(TIMEOUT).unwrap();
+    match clip {
+        CapturedClip::Text { text, selection, .. } => {
+            assert_eq!(text, "hello");
+            assert_eq!(selection, Selection::Clipboard);
+        }
+        other => panic!("expected a text clip, got {:?}", other),
+    }
+    assert_eq!(origin.app.as_deref(), Some("firefox"));
+
+    // Reading the same value again is not a new copy
+    clipboard.copy_text("hello");
+    monitor.expect_only_sentinel();
+    monitor.stop();
+}
+
+#[test]
+fn records_images_and_files() {
+    let clipboard = MockBackend::new();
+    let monitor = start(&clipboard, MonitorOptions::default());
+
+    let image = ImageData { width: 2, height: 1, rgba: vec![255, 0, 0, 255, 0, 0, 255, 255] };
+    clipboard.copy(MockContent::Image(image.clone()));
+    match monitor.next() {
+        CapturedClip::Image { width, height, rgba } => assert_eq!(ImageData { width, height, rgba }, image),
+        other => panic!("expected an image clip, got {:?}", other),
+    }
+
+    let paths = vec![PathBuf::from("/tmp/a.txt"), PathBuf::from("/tmp/b c.png")];
+    clipboard.copy(MockContent::Files(paths.clone()));
+    match monitor.next() {
+        CapturedClip::Files(files) => assert_eq!(files, paths),
+        other => panic!("expected a files clip, got {:?}", other),
+    }
+    monitor.stop();
+}
+
+#[test]
+fn skips_copies_marked_secret() {
+    let clipboard = MockBackend::new();
+    let monitor = start(&clipboard, MonitorOptions::default());
+
+    clipboard.copy(MockContent::Text {
+        text: "hunter2".to_string(),
+        formats: vec![ClipFormat::new("x-kde-passwordManagerHint", b"secret".to_vec())],
+    });
+    monitor.expect_only_sentinel();
+    monitor.stop();
+}
+
+#[test]
+fn skips_excluded_applications() {
+    let clipboard = MockBackend::new();
+    let options = MonitorOptions { excluded_apps: vec!["KeePassXC".to_string()], ..Default::default() };
+    let monitor = start(&clipboard, options);
+
+    clipboard.set_origin(app("keepassxc"));
+    clipboard.copy_text("hunter2");
+    clipboard.set_origin(app("firefox"));
+    monitor.expect_only_sentinel();
+    monitor.stop();
+}
+
+#[test]
+fn coalesces_bursts_of_writes() {
+    let clipboard = MockBackend::new();
+    let options = MonitorOptions { coalesce_window: Duration::from_millis(300), skip_cleared: true, ..Default::default() };
+    let monitor = start(&clipboard, options);
+
+    for text in ["h", "he", "hello"] {
+        clipboard.copy_text(text);
+    }
+    assert_eq!(monitor.next_text(), "hello");
+
+    // Copied and cleared again within the window, like a password manager does
+    clipboard.copy_text("one-time code");
+    clipboard.clear();
+    monitor.expect_only_sentinel();
+    monitor.stop();
+}
+
+#[test]
+fn serves_the_last_clip_without_recording_it_again() {
+    let clipboard = MockBackend::new();
+    let options = MonitorOptions { keep_alive: true, ..Default::default() };
+    let monitor = start(&clipboard, options);
+
+    clipboard.copy_text("kept");
+    assert_eq!(monitor.next_text(), "kept");
+
+    // The owner exits and the clipboard empties
+    clipboard.clear();
+    wait_for(|| clipboard_text(&clipboard).as_deref() == Some("kept"));
+    monitor.expect_only_sentinel();
+    monitor.stop();
+}
+
+#[test]
+fn restores_the_last_clip_without_recording_it_again() {
+    let clipboard = MockBackend::new();
+    let restore = CapturedClip::Text { text: "restored".to_string(), formats: Vec::new(), selection: Selection::Clipboard };
+    let monitor = start(&clipboard, MonitorOptions { restore: Some(restore), ..Default::default() });
+
+    wait_for(|| clipboard_text(&clipboard).as_deref() == Some("restored"));
+    monitor.expect_only_sentinel();
+    monitor.stop();
+
+    // Something copied before the start is left alone
+    let clipboard = MockBackend::new();
+    clipboard.copy_text("newer");
+    let restore = CapturedClip::Text { text: "restored".to_string(), formats: Vec::new(), selection: Selection::Clipboard };
+    let monitor = start(&clipboard, MonitorOptions { restore: Some(restore), ..Default::default() });
+    assert_eq!(monitor.next_text(), "newer");
+    monitor.stop();
+}
+
+#[test]
+fn mirrored_selections_are_recorded_once() {
+    let mut clipboard = MockBackend::new();
+    let options = MonitorOptions { watch_primary: true, sync: SelectionSync::Both, ..Default::default() };
+    let monitor = start(&clipboard, options);
+
+    clipboard.copy_text("copied");
+    assert_eq!(monitor.next_text(), "copied");
+    wait_for(|| clipboard.get_text(Selection::Primary).unwrap().as_deref() == Some("copied"));
+
+    clipboard.select("selected");
+    match monitor.next() {
+        CapturedClip::Text { text, selection, .. } => {
+            assert_eq!(text, "selected");
+            assert_eq!(selection, Selection::Primary);
+        }
+        other => panic!("expected a text clip, got {:?}", other),
+    }
+    wait_for(|| clipboard_text(&clipboard).as_deref() == Some("selected"));
+    monitor.expect_only_sentinel();
+    monitor.stop();
+}
+
+#[test]
+fn skips_copies_made_while_paused() {
+    let clipboard = MockBackend::new();
+    clipboard.copy_text("copied before the start");
+    let monitor = start(&clipboard, MonitorOptions { start_paused: true, ..Default::default() });
+
+    clipboard.copy_text("copied while paused");
+    monitor.commands.send(MonitorCommand::Resume).unwrap();
+    // Commands and clipboard changes reach the monitor on different paths
+    thread::sleep(Duration::from_millis(200));
+    monitor.expect_only_sentinel();
+
+    monitor.commands.send(MonitorCommand::Pause).unwrap();
+    thread::sleep(Duration::from_millis(200));
+    clipboard.copy_text("copied during a pause");
+    monitor.commands.send(MonitorCommand::Resume).unwrap();
+    thread::sleep(Duration::from_millis(200));
+    clipboard.copy_text("after resuming");
+    assert_eq!(monitor.next_text(), "after resuming");
+    monitor.stop();
+}
+
+#[test]
+fn applies_new_settings_while_running() {
+    let clipboard = MockBackend::new();
+    let monitor = start(&clipboard, MonitorOptions::default());
+
+    clipboard.set_origin(app("keepassxc"));
+    clipboard.copy_text("recorded");
+    assert_eq!(monitor.next_text(), "recorded");
+
+    let options = MonitorOptions { excluded_apps: vec!["KeePassXC".to_string()], watch_primary: true, ..Default::default() };
+    monitor.commands.send(MonitorCommand::Reconfigure(options)).unwrap();
+    thread::sleep(Duration::from_millis(200));
+    clipboard.copy_text("hunter2");
+    clipboard.set_origin(app("firefox"));
+    monitor.expect_only_sentinel();
+
+    clipboard.select("selected");
+    match monitor.next() {
+        CapturedClip::Text { text, selection, .. } => {
+            assert_eq!(text, "selected");
+            assert_eq!(selection, Selection::Primary);
+        }
+        other => panic!("expected a text clip, got {:?}", other),
+    }
+    monitor.stop();
+}
@@ -0,0 +1,256 @@
+// The search box language: parsing, autocomplete and the SQL it compiles to, run against an
+// in-memory vault.
+use chrono::NaiveDate;
+use clipvault::db;
+use clipvault::models::ClipOrigin;
+use clipvault::utils::fuzzy::FuzzyIndex;
+use clipvault::utils::search::{ Comparison, Filter, KindFilter, SearchQuery, Term, completions };
+use rusqlite::{ Connection, params };
+
+fn term(filter: Filter) -> Term {
+    Term { negated: false, filter }
+}
+
+fn negated(filter: Filter) -> Term {
+    Term { negated: true, filter }
+}
+
+fn text(text: &str) -> Filter {
+    Filter::Text(text.to_string())
+}
+
+fn date(day: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn words_phrases_and_negation() {
+    let query = SearchQuery::parse(r#"invoice "exact  phrase" -draft -"not this""#);
+    assert_eq!(
+        query.groups,
+        vec![vec![term(text("invoice")), term(text("exact  phrase")), negated(text("draft")), negated(text("not this"))]]
+    );
+    assert!(query.errors.is_empty());
+}
+
+#[test]
+fn qualifiers() {
+    let query = SearchQuery::parse(
+        r#"tag:work -tag:old pinned:yes kind:url after:2025-01-01 before:2025-02-01 len:>500 app:firefox window:"Rust docs" host:laptop"#,
+    );
+    assert_eq!(
+        query.groups,
+        vec![vec![
+            term(Filter::Tag("work".to_string())),
+            negated(Filter::Tag("old".to_string())),
+            term(Filter::Pinned(true)),
+            term(Filter::Kind(KindFilter::Url)),
+            term(Filter::After(date("2025-01-01"))),
+            term(Filter::Before(date("2025-02-01"))),
+            term(Filter::Length(Comparison::Greater, 500)),
+            term(Filter::App("firefox".to_string())),
+            term(Filter::Window("Rust docs".to_string())),
+            term(Filter::Host("laptop".to_string())),
+        ]]
+    );
+}
+
+#[test]
+fn qualifier_names_ignore_case() {
+    assert_eq!(SearchQuery::parse("TAG:work").groups, vec![vec![term(Filter::Tag("work".to_string()))]]);
+    assert_eq!(SearchQuery::parse("Pinned:no").groups, vec![vec![term(Filter::Pinned(false))]]);
+}
+
+#[test]
+fn length_comparisons() {
+    let length = |value: &str| SearchQuery::parse(&format!("len:{}", value)).groups[0][0].filter.clone();
+    assert_eq!(length("<20"), Filter::Length(Comparison::Less, 20));
+    assert_eq!(length("<=20"), Filter::Length(Comparison::LessOrEqual, 20));
+    assert_eq!(length("=20"), Filter::Length(Comparison::Equal, 20));
+    assert_eq!(length("20"), Filter::Length(Comparison::Equal, 20));
+    assert_eq!(length(">=20"), Filter::Length(Comparison::GreaterOrEqual, 20));
+}
+
+#[test]
+fn or_separates_alternatives() {
+    let query = SearchQuery::parse("tag:work invoice OR pinned:yes");
+    assert_eq!(
+        query.groups,
+        vec![vec![term(Filter::Tag("work".to_string())), term(text("invoice"))], vec![term(Filter::Pinned(true))]]
+    );
+
+    // Stray ORs are ignored, lowercase or quoted ones are words
+    assert_eq!(SearchQuery::parse("OR a OR").groups, vec![vec![term(text("a"))]]);
+    assert_eq!(SearchQuery::parse(r#"a or "OR""#).groups, vec![vec![term(text("a")), term(text("or")), term(text("OR"))]]);
+}
+
+#[test]
+fn things_that_are_not_qualifiers_stay_text() {
+    let query = SearchQuery::parse(r#"https://example.com "tag:work" 12:30"#);
+    assert_eq!(
+        query.groups,
+        vec![vec![term(text("https://example.com")), term(text("tag:work")), term(text("12:30"))]]
+    );
+    assert!(query.errors.is_empty());
+}
+
+#[test]
+fn bad_values_are_searched_as_text_and_reported() {
+    let query = SearchQuery::parse("after:yesterday len:lots pinned:maybe kind:video");
+    assert_eq!(
+        query.groups,
+        vec![vec![
+            term(text("after:yesterday")),
+            term(text("len:lots")),
+            term(text("pinned:maybe")),
+            term(text("kind:video")),
+        ]]
+    );
+    assert_eq!(query.errors.len(), 4);
+    assert!(query.errors[0].starts_with("after:"), "{}", query.errors[0]);
+}
+
+#[test]
+fn qualifiers_being_typed_are_skipped() {
+    assert_eq!(SearchQuery::parse("invoice tag:").groups, vec![vec![term(text("invoice"))]]);
+    assert!(SearchQuery::parse("tag: - \"\"").is_empty());
+}
+
+#[test]
+fn split_text_for_fuzzy_mode() {
+    let alternatives = SearchQuery::parse("kubctl -draft tag:work pods").split_text();
+    assert_eq!(alternatives.len(), 1);
+    assert_eq!(alternatives[0].0, "kubctl pods");
+    assert_eq!(alternatives[0].1.groups, vec![vec![negated(text("draft")), term(Filter::Tag("work".to_string()))]]);
+
+    let alternatives = SearchQuery::parse("kubctl pods").split_text();
+    assert_eq!(alternatives[0].0, "kubctl pods");
+    assert!(alternatives[0].1.is_empty());
+
+    // Each alternative keeps its own words and qualifiers
+    let alternatives = SearchQuery::parse("foo OR bar tag:x OR pinned:yes").split_text();
+    let words: Vec<&str> = alternatives.iter().map(|(words, _)| words.as_str()).collect();
+    assert_eq!(words, ["foo", "bar", ""]);
+    assert!(alternatives[0].1.is_empty());
+    assert_eq!(alternatives[1].1.groups, vec![vec![term(Filter::Tag("x".to_string()))]]);
+    assert_eq!(alternatives[2].1.groups, vec![vec![term(Filter::Pinned(true))]]);
+}
+
+#[test]
+fn completes_qualifiers() {
+    let suggestions = completions("invoice -ta", &[]);
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!(suggestions[0].label, "tag:");
+    assert_eq!(suggestions[0].query, "invoice -tag:");
+
+    let labels: Vec<String> = completions("a", &[]).into_iter().map(|suggestion| suggestion.label).collect();
+    assert_eq!(labels, ["after:", "app:"]);
+
+    assert!(completions("invoice ", &[]).is_empty());
+    assert!(completions("\"open phr", &[]).is_empty());
+
+    let suggestions = completions("foo\u{a0}ta", &[]);
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!(suggestions[0].query, "foo\u{a0}tag:");
+}
+
+#[test]
+fn completes_values() {
+    let tags = ["work".to_string(), "Weekend trip".to_string(), "old".to_string()];
+    let queries: Vec<String> = completions("tag:w", &tags).into_iter().map(|suggestion| suggestion.query).collect();
+    assert_eq!(queries, ["tag:work ", "tag:\"Weekend trip\" "]);
+
+    assert!(completions("tag:work", &tags).is_empty());
+    assert_eq!(completions("kind:u", &[])[0].query, "kind:url ");
+    assert_eq!(completions("x pinned:", &[]).len(), 2);
+    assert!(completions("app:fi", &tags).is_empty());
+}
+
+// Clips with known content, tags, pins, times and origins
+fn vault() -> Connection {
+    db::set_quiet(true);
+    let conn = db::init_db_at(":memory:").unwrap();
+    let add = |content: &str, tags: &[&str], pin: bool, day: &str| {
+        let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+        let id = db::add_text_clip(&conn, content, None, None, &tags, pin).unwrap();
+        let noon = date(day).and_hms_opt(12, 0, 0).unwrap().and_local_timezone(chrono::Local).unwrap();
+        conn.execute("UPDATE clips SET timestamp = ?1 WHERE id = ?2", params![noon.timestamp(), id]).unwrap();
+        id
+    };
+    let invoice = add("invoice 42 for ACME", &["work"], true, "2025-01-10");
+    add("https://example.com/docs", &["work", "old"], false, "2024-12-24");
+    add("shopping list: eggs, 100% cocoa", &[], false, "2025-03-01");
+    add(&"x".repeat(600), &["old"], false, "2025-02-15");
+    db::save_clip_origin(
+        &conn,
+        invoice,
+        &ClipOrigin { app: Some("Firefox".to_string()), hostname: Some("laptop".to_string()), ..Default::default() },
+    )
+    .unwrap();
+    conn
+}
+
+fn search(conn: &Connection, query: &str) -> Vec<String> {
+    let mut found: Vec<String> = db::search_clips(conn, query, Some(100))
+        .unwrap()
+        .into_iter()
+        .map(|clip| clip.1.chars().take(20).collect())
+        .collect();
+    found.sort();
+    found
+}
+
+#[test]
+fn compiles_to_sql() {
+    let conn = vault();
+    let long = "x".repeat(20);
+
+    assert_eq!(search(&conn, "invoice acme"), ["invoice 42 for ACME"]);
+    assert_eq!(search(&conn, "\"42 for\""), ["invoice 42 for ACME"]);
+    assert!(search(&conn, "\"for 42\"").is_empty());
+    assert_eq!(search(&conn, "tag:work"), ["https://example.com/", "invoice 42 for ACME"]);
+    assert_eq!(search(&conn, "tag:WORK -tag:old"), ["invoice 42 for ACME"]);
+    assert_eq!(search(&conn, "pinned:yes"), ["invoice 42 for ACME"]);
+    assert_eq!(search(&conn, "pinned:no kind:url"), ["https://example.com/"]);
+    assert_eq!(search(&conn, "after:2025-01-10 before:2025-03-01"), ["invoice 42 for ACME", long.as_str()]);
+    assert_eq!(search(&conn, "before:2025-01-01"), ["https://example.com/"]);
+    assert_eq!(search(&conn, "len:>500"), [long.as_str()]);
+    assert_eq!(search(&conn, "app:firefox host:lap"), ["invoice 42 for ACME"]);
+    assert_eq!(search(&conn, "-app:firefox tag:work"), ["https://example.com/"]);
+    assert_eq!(search(&conn, "eggs OR tag:old"), ["https://example.com/", "shopping list: eggs,", long.as_str()]);
+}
+
+#[test]
+fn like_wildcards_are_literal() {
+    let conn = vault();
+    assert_eq!(search(&conn, "100%"), ["shopping list: eggs,"]);
+    assert!(search(&conn, "1_0").is_empty());
+}
+
+// In ranked order, unlike `search`
+fn fuzzy_search(conn: &Connection, index: &mut FuzzyIndex, query: &str) -> Vec<String> {
+    db::fuzzy_search_clips(conn, index, query, Some(100))
+        .unwrap()
+        .into_iter()
+        .map(|clip| clip.1.chars().take(20).collect())
+        .collect()
+}
+
+#[test]
+fn fuzzy_mode_ranks_each_alternative() {
+    let conn = vault();
+    let mut index = FuzzyIndex::default();
+    let long = "x".repeat(20);
+
+    assert_eq!(fuzzy_search(&conn, &mut index, "invce"), ["invoice 42 for ACME"]);
+    assert_eq!(fuzzy_search(&conn, &mut index, "invce tag:work -tag:old"), ["invoice 42 for ACME"]);
+    assert_eq!(fuzzy_search(&conn, &mut index, "invce OR shpng"), ["invoice 42 for ACME", "shopping list: eggs,"]);
+    assert_eq!(fuzzy_search(&conn, &mut index, "invce -tag:work OR shpng"), ["shopping list: eggs,"]);
+    // Alternatives without words list their clips newest first, merged with the ranked ones
+    assert_eq!(
+        fuzzy_search(&conn, &mut index, "eggs OR tag:old"),
+        ["shopping list: eggs,", long.as_str(), "https://example.com/"]
+    );
+    // Only qualifiers, searched like in substring mode
+    assert_eq!(fuzzy_search(&conn, &mut index, "pinned:yes"), ["invoice 42 for ACME"]);
+}
@@ -0,0 +1,83 @@
+// Link cleaning: which parameters go, which stay and that the rest of the link is left
+// exactly as copied.
+use clipvault::utils::urls::{ UrlCleaning, UrlRule };
+
+fn clean(text: &str) -> Option<String> {
+    UrlCleaning::default().clean(text)
+}
+
+#[test]
+fn removes_tracking_parameters() {
+    assert_eq!(
+        clean("https://example.com/post?utm_source=news&utm_medium=email&id=42").as_deref(),
+        Some("https://example.com/post?id=42")
+    );
+    assert_eq!(clean("https://example.com/?fbclid=IwAR0abc").as_deref(), Some("https://example.com/"));
+    assert_eq!(clean("http://example.com/a?gclid=1&utm_campaign=x").as_deref(), Some("http://example.com/a"));
+}
+
+#[test]
+fn keeps_everything_else_as_copied() {
+    // Order, encoding, empty values and the fragment survive
+    assert_eq!(
+        clean("https://example.com/search?q=a%20b&utm_term=x&page=&sort=new#results").as_deref(),
+        Some("https://example.com/search?q=a%20b&page=&sort=new#results")
+    );
+    // Surrounding whitespace is kept
+    assert_eq!(clean("  https://example.com/?fbclid=1\n").as_deref(), Some("  https://example.com/\n"));
+    // A parameter merely starting like a tracking one is not one
+    assert_eq!(clean("https://example.com/?fbclid_keep=1&utmost=2"), None);
+}
+
+#[test]
+fn leaves_other_text_alone() {
+    assert_eq!(clean("https://example.com/?id=42"), None);
+    assert_eq!(clean("https://example.com/page"), None);
+    assert_eq!(clean("see https://example.com/?utm_source=x"), None);
+    assert_eq!(clean("ftp://example.com/?utm_source=x"), None);
+    assert_eq!(clean("utm_source=x"), None);
+
+    let disabled = UrlCleaning { enabled: false, ..Default::default() };
+    assert_eq!(disabled.clean("https://example.com/?utm_source=x"), None);
+}
+
+#[test]
+fn site_rules_apply_to_their_domains_only() {
+    assert_eq!(
+        clean("https://www.youtube.com/watch?v=dQw4w9WgXcQ&si=abc&feature=share").as_deref(),
+        Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+    );
+    assert_eq!(clean("https://youtu.be/dQw4w9WgXcQ?si=abc").as_deref(), Some("https://youtu.be/dQw4w9WgXcQ"));
+    assert_eq!(clean("https://example.com/watch?si=abc"), None);
+    // "amazon.*" covers every country domain
+    assert_eq!(
+        clean("https://www.amazon.co.uk/dp/B000?ref=sr_1&pd_rd_w=x&th=1").as_deref(),
+        Some("https://www.amazon.co.uk/dp/B000?th=1")
+    );
+    assert_eq!(clean("https://notamazon.com/dp/B000?ref=sr_1"), None);
+    // Hosts are matched case-insensitively, without the user info or port
+    assert_eq!(clean("https://user@X.COM:443/post?s=20&t=abc").as_deref(), Some("https://user@X.COM:443/post"));
+}
+
+#[test]
+fn user_rules() {
+    let rules = ["shop.example: ref, aff_*", "*: session"].into_iter().filter_map(UrlRule::parse_line).collect();
+    let cleaning = UrlCleaning { builtin_rules: false, rules, ..Default::default() };
+    assert_eq!(
+        cleaning.clean("https://www.shop.example/item?ref=home&aff_id=7&color=red&session=1").as_deref(),
+        Some("https://www.shop.example/item?color=red")
+    );
+    assert_eq!(cleaning.clean("https://other.example/?ref=home&session=1").as_deref(), Some("https://other.example/?ref=home"));
+    // The built-in lists are off
+    assert_eq!(cleaning.clean("https://other.example/?utm_source=x"), None);
+}
+
+#[test]
+fn parses_rule_lines() {
+    let rule = UrlRule::parse_line(" *.Example.com : ref,  utm_* ").unwrap();
+    assert_eq!(rule, UrlRule { domain: "example.com".to_string(), params: vec!["ref".to_string(), "utm_*".to_string()] });
+    assert_eq!(rule.to_line(), "example.com: ref, utm_*");
+    assert_eq!(UrlRule::parse_line("example.com"), None);
+    assert_eq!(UrlRule::parse_line("example.com:"), None);
+    assert_eq!(UrlRule::parse_line(": ref"), None);
+}
@@ -0,0 +1,140 @@
+// Runs the XFixes selection watcher and the monitor against a private Xvfb server, with a
+// plain x11rb client taking ownership of CLIPBOARD the way an application copying text does.
+// Needs Xvfb, so it only runs when asked for: `cargo test --test x11_watch -- --ignored`.
+#![cfg(target_os = "linux")]
+
+use std::{
+    env,
+    io::{ BufRead, BufReader },
+    process::{ Child, Command, Stdio },
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use clipvault::clipboard::{ CapturedClip, ClipboardBackend, MonitorCommand, MonitorOptions, Wake, monitor_with_backend };
+use clipvault::clipboard::backend::ArboardBackend;
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{
+    AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, PropMode, SELECTION_NOTIFY_EVENT, SelectionNotifyEvent,
+    WindowClass,
+};
+use x11rb::wrapper::ConnectionExt as _;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Xvfb {
+    server: Child,
+    display: String,
+}
+
+impl Xvfb {
+    // Xvfb picks a free display number and writes it to the given fd
+    fn start() -> Self {
+        let mut server = Command::new("Xvfb")
+            .args(["-displayfd", "1", "-nolisten", "tcp", "-screen", "0", "640x480x24"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Xvfb must be installed to run this test");
+        let mut number = String::new();
+        BufReader::new(server.stdout.take().unwrap()).read_line(&mut number).unwrap();
+        Self { server, display: format!(":{}", number.trim()) }
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.server.kill();
+        let _ = self.server.wait();
+    }
+}
+
+// Take CLIPBOARD with `text` on a new connection and answer paste requests for it until
+// another client takes the selection over. Returns once the selection is owned.
+fn copy_text(text: &'static str) {
+    let (owned_tx, owned) = mpsc::channel();
+    thread::spawn(move || {
+        let (conn, screen) = x11rb::connect(None).unwrap();
+        let root = conn.setup().roots[screen].root;
+        let window = conn.generate_id().unwrap();
+        conn.create_window(0, window, root, 0, 0, 1, 1, 0, WindowClass::INPUT_ONLY, 0, &CreateWindowAux::new())
+            .unwrap();
+        let atom = |name: &[u8]| conn.intern_atom(false, name).unwrap().reply().unwrap().atom;
+        let (clipboard, targets, utf8) = (atom(b"CLIPBOARD"), atom(b"TARGETS"), atom(b"UTF8_STRING"));
+
+        conn.set_selection_owner(window, clipboard, x11rb::CURRENT_TIME).unwrap();
+        assert_eq!(conn.get_selection_owner(clipboard).unwrap().reply().unwrap().owner, window);
+        owned_tx.send(()).unwrap();
+
+        loop {
+            match conn.wait_for_event().unwrap() {
+                Event::SelectionRequest(request) => {
+                    // Anything but the text and the list of targets is refused
+                    let property = if request.target == targets {
+                        conn.change_property32(PropMode::REPLACE, request.requestor, request.property, AtomEnum::ATOM, &[
+                            targets, utf8,
+                        ])
+                        .unwrap();
+                        request.property
+                    } else if request.target == utf8 {
+                        conn.change_property8(PropMode::REPLACE, request.requestor, request.property, utf8, text.as_bytes())
+                            .unwrap();
+                        request.property
+                    } else {
+                        x11rb::NONE
+                    };
+                    let notify = SelectionNotifyEvent {
+                        response_type: SELECTION_NOTIFY_EVENT,
+                        sequence: 0,
+                        time: request.time,
+                        requestor: request.requestor,
+                        selection: request.selection,
+                        target: request.target,
+                        property,
+                    };
+                    conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify).unwrap();
+                    conn.flush().unwrap();
+                }
+                Event::SelectionClear(_) => return,
+                _ => {}
+            }
+        }
+    });
+    owned.recv_timeout(TIMEOUT).expect("CLIPBOARD to be taken");
+}
+
+#[test]
+#[ignore = "starts Xvfb; run with `cargo test --test x11_watch -- --ignored`"]
+fn xfixes_changes_wake_the_monitor() {
+    let xvfb = Xvfb::start();
+    // The only test in this binary, nothing else reads the environment concurrently
+    unsafe {
+        env::set_var("DISPLAY", &xvfb.display);
+        env::remove_var("WAYLAND_DISPLAY");
+    }
+
+    let (wake_tx, wake_rx) = mpsc::channel();
+    let mut backend = ArboardBackend::new().unwrap();
+    assert_eq!(backend.watch(false, wake_tx), Some("XFixes"));
+    copy_text("first copy");
+    assert!(matches!(wake_rx.recv_timeout(TIMEOUT), Ok(Wake::Changed)));
+
+    // With change events the monitor doesn't poll, so the second copy is only seen through them
+    let (clip_tx, clips) = mpsc::channel();
+    let backend = Box::new(ArboardBackend::new().unwrap());
+    let (commands, handle) = monitor_with_backend(backend, MonitorOptions::default(), move |clip, _, _| {
+        let _ = clip_tx.send(clip);
+    })
+    .unwrap();
+    let next_text = || match clips.recv_timeout(TIMEOUT).expect("a clip to be recorded") {
+        CapturedClip::Text { text, .. } => text,
+        other => panic!("expected a text clip, got {:?}", other),
+    };
+    assert_eq!(next_text(), "first copy");
+    copy_text("second copy");
+    assert_eq!(next_text(), "second copy");
+
+    commands.send(MonitorCommand::Stop).unwrap();
+    handle.join().unwrap();
+}